@@ -3,21 +3,294 @@ extern crate alloc;
 
 use alloc::string::String;
 use alloc::vec::Vec;
-use alloy_primitives::{Address, U256, U8, FixedBytes, keccak256};
-use alloy_sol_types::sol;
+use alloy_primitives::{address, b256, uint, Address, B256, U256, U64, U8, FixedBytes, keccak256};
+use alloy_sol_types::{eip712_domain, sol, Eip712Domain, SolCall, SolStruct};
 use stylus_sdk::{
+    abi::{AbiType, Bytes, ConstString},
     prelude::*,
-    stylus_core::log,
+    storage::{StorageU256, StorageVec},
+    stylus_core::{calls::context::Call, log},
 };
 
+// ── Roles ─────────────────────────────────────────────────────────────────────
+// Every role is administered by DEFAULT_ADMIN_ROLE. Ids match OpenZeppelin's
+// AccessControl convention: keccak256 of the role name.
+
+/// Grants and revokes roles and manages tokens, expiries and periods.
+const DEFAULT_ADMIN_ROLE: B256 = B256::ZERO;
+/// Calls `mark_as_paid` once the fiat payout has gone out. keccak256("SETTLER_ROLE")
+const SETTLER_ROLE: B256 =
+    b256!("6666bf5bfee463d10a7fc50448047f8a53b7762d7e28fbc5c643182785f3fd3f");
+/// May be named the treasury that receives accepted funds. keccak256("TREASURY_ROLE")
+const TREASURY_ROLE: B256 =
+    b256!("e1dcbdb91df27212a29bc27177c840cf2f819ecf2187432e1fac86c2dd5dfca9");
+/// Halts and resumes entrypoints in an emergency. keccak256("PAUSER_ROLE")
+const PAUSER_ROLE: B256 =
+    b256!("65d7a28e3265b37a6474929f336521b332c1681b933f6cb9f3376673440d862a");
+/// Manages currencies, oracles and the rate quoter. keccak256("RATE_ADMIN_ROLE")
+const RATE_ADMIN_ROLE: B256 =
+    b256!("af977a80e23262c8ff5f86cefbb6a4f6250c151974ee28fb039b0005ddf0d2cd");
+/// Rules on disputes. keccak256("ARBITRATOR_ROLE")
+const ARBITRATOR_ROLE: B256 =
+    b256!("16ceee8289685dd2a02b9c8ae81d2df373176ce53519e6284e2a2950d6546ffa");
+/// Proposes and executes implementation upgrades. keccak256("UPGRADER_ROLE")
+const UPGRADER_ROLE: B256 =
+    b256!("189ab7a9244df0848122154315af71fe140f3db0fe014031783b0946b8c9d2e3");
+/// Every role above; walked when the admin hands over to a new address.
+const ALL_ROLES: [B256; 7] = [
+    DEFAULT_ADMIN_ROLE,
+    SETTLER_ROLE,
+    TREASURY_ROLE,
+    PAUSER_ROLE,
+    RATE_ADMIN_ROLE,
+    ARBITRATOR_ROLE,
+    UPGRADER_ROLE,
+];
+
+// ── Payment status ────────────────────────────────────────────────────────────
+// Stored in `Payment.status` as its discriminant, so values are part of the
+// storage layout and the ABI; new states go at the end.
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+enum PaymentStatus {
+    Pending = 0,
+    Accepted = 1,
+    Rejected = 2,
+    Paid = 3,
+    Expired = 4,
+    Cancelled = 5,
+    Disputed = 6,
+    Refunded = 7,
+    Released = 8,
+    Voided = 9,
+}
+
+impl PaymentStatus {
+    fn from_u8(value: u8) -> Option<Self> {
+        use PaymentStatus::*;
+        Some(match value {
+            0 => Pending,
+            1 => Accepted,
+            2 => Rejected,
+            3 => Paid,
+            4 => Expired,
+            5 => Cancelled,
+            6 => Disputed,
+            7 => Refunded,
+            8 => Released,
+            9 => Voided,
+            _ => return None,
+        })
+    }
+
+    /// The lifecycle's transition table. Every status write is checked against it.
+    /// Disputed has no exits here: only `leave_dispute` moves a payment out.
+    fn can_become(self, to: Self) -> bool {
+        use PaymentStatus::*;
+        matches!(
+            (self, to),
+            (Pending, Accepted | Rejected | Expired | Cancelled | Voided)
+                | (Accepted, Paid | Released | Disputed)
+                | (Paid, Disputed | Refunded)
+                // further partial refunds
+                | (Refunded, Refunded)
+        )
+    }
+
+    /// Where a withdrawn or ruled dispute may leave the payment.
+    fn can_leave_dispute_to(to: Self) -> bool {
+        matches!(to, PaymentStatus::Accepted | PaymentStatus::Paid | PaymentStatus::Refunded)
+    }
+}
+
+// ── Upgrades ──────────────────────────────────────────────────────────────────
+// SettlX runs behind an ERC-1967 proxy and upgrades itself UUPS-style: the
+// proxy only forwards calls, and `execute_upgrade` rewrites the proxy's
+// implementation slot.
+
+/// ERC-1967 implementation slot: keccak256("eip1967.proxy.implementation") - 1.
+const IMPLEMENTATION_SLOT: B256 =
+    b256!("360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc");
+/// Version this build initialises to; `reinitialize` brings older storage up to it.
+const VERSION: u64 = 3;
+/// Time between `propose_upgrade` and `execute_upgrade`.
+const DEFAULT_UPGRADE_DELAY: u64 = 2 * 24 * 60 * 60;
+/// Floor for the upgrade delay, so the timelock cannot be switched off.
+const MIN_UPGRADE_DELAY: u64 = 24 * 60 * 60;
+
+// migration_state values
+const MIGRATION_NONE: u8 = 0;
+const MIGRATION_ACTIVE: u8 = 1;
+const MIGRATION_SEALED: u8 = 2;
+
+/// Expiry applied when `pay_merchant` is called with `expires_in == 0`.
+const DEFAULT_PAYMENT_TTL: u64 = 7 * 24 * 60 * 60;
+/// Upper bound on how long a payer can leave funds waiting for the merchant.
+const MAX_PAYMENT_TTL: u64 = 30 * 24 * 60 * 60;
+/// Amounts are normalised to this many decimals before any rate math.
+const NORMALISED_DECIMALS: u8 = 18;
+/// Settlement currency used when neither the caller nor the merchant's profile picks one.
+const DEFAULT_CURRENCY: FixedBytes<3> = FixedBytes(*b"NGN");
+/// Denominator for basis-point values such as the oracle deviation band.
+const BPS_DENOMINATOR: u64 = 10_000;
+/// Ceiling on the protocol fee: 10%.
+const MAX_FEE_BPS: u64 = 1_000;
+/// Ceiling on a pricing plan's FX spread: 10%.
+const MAX_FX_SPREAD_BPS: u64 = 1_000;
+/// Most items a single batch call will process.
+const MAX_BATCH_SIZE: usize = 100;
+/// Most ids a paginated view returns per call.
+const MAX_PAGE_SIZE: usize = 100;
+/// Most index entries a status-filtered view inspects per call.
+const MAX_PAGE_SCAN: usize = 500;
+/// ecrecover precompile.
+const ECRECOVER: Address = address!("0000000000000000000000000000000000000001");
+/// secp256k1n / 2 — signatures with a larger `s` are malleable and rejected.
+const SECP256K1_HALF_ORDER: U256 =
+    uint!(0x7FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF5D576E7357A4501DDFE92F46681B20A0_U256);
+/// How long settlers have to settle an escrowed payment before it can be released.
+const DEFAULT_SETTLEMENT_PERIOD: u64 = 3 * 24 * 60 * 60;
+/// How long both parties can keep submitting evidence after a dispute opens.
+const DEFAULT_DISPUTE_PERIOD: u64 = 3 * 24 * 60 * 60;
+/// How long after settlement a Paid payment can still be disputed.
+const DEFAULT_PAID_DISPUTE_WINDOW: u64 = 30 * 24 * 60 * 60;
+/// How long a pause must last before payers can pull Pending funds themselves.
+const DEFAULT_PAUSE_GRACE_PERIOD: u64 = 3 * 24 * 60 * 60;
+
+// Pause flags; `pause` and `unpause` take any combination of them.
+/// `pay_merchant`, `cancel_payment` and `reclaim_expired_payment`.
+const PAUSE_PAYMENTS: u8 = 1 << 0;
+/// `accept_payment_with_rate`, `accept_payment_with_quote` and `reject_payment`.
+const PAUSE_ACCEPTANCE: u8 = 1 << 1;
+/// `mark_as_paid`, `release_overdue_escrow` and `rule_on_dispute`.
+const PAUSE_SETTLEMENT: u8 = 1 << 2;
+const PAUSE_ALL: u8 = PAUSE_PAYMENTS | PAUSE_ACCEPTANCE | PAUSE_SETTLEMENT;
+
+// Dispute.state values
+const DISPUTE_NONE: u8 = 0;
+const DISPUTE_OPEN: u8 = 1;
+const DISPUTE_RESOLVED: u8 = 2;
+const DISPUTE_WITHDRAWN: u8 = 3;
+
+// Dispute.ruling values
+const RULING_REFUND_PAYER: u8 = 1;
+const RULING_FORCE_SETTLE: u8 = 2;
+const RULING_COMPENSATE_MERCHANT: u8 = 3;
+
 // ── ERC20 interface ───────────────────────────────────────────────────────────
-sol_interface! {
+// Encoded by hand instead of through sol_interface! so the safe-transfer layer
+// can tell a `false` return apart from a token that returns no data at all.
+sol! {
     interface IERC20 {
         function transferFrom(address from, address to, uint256 amount) external returns (bool);
         function transfer(address to, uint256 amount) external returns (bool);
     }
 }
 
+// ── Chainlink-style price feed ────────────────────────────────────────────────
+// Used both for per-currency FX feeds and for the L2 sequencer uptime feed.
+sol! {
+    interface AggregatorV3Interface {
+        function decimals() external view returns (uint8);
+        function latestRoundData() external view returns (uint80 roundId, int256 answer, uint256 startedAt, uint256 updatedAt, uint80 answeredInRound);
+    }
+}
+
+// ── ERC-1822 proxiable implementations ───────────────────────────────────────
+sol! {
+    interface IERC1822Proxiable {
+        function proxiableUUID() external view returns (bytes32);
+    }
+}
+
+// ── Legacy SettlX (legacy/set-leg.rs) ────────────────────────────────────────
+// Read-only view of an old deployment, used to import its history.
+sol! {
+    interface ILegacySettlX {
+        function getPayment(uint256 payment_id) external view returns (uint256, address, address, uint256, uint256, bytes32, uint8);
+        function getMerchantBankDetails(address merchant) external view returns (bytes32, bytes32, bytes32);
+    }
+}
+
+// ── EIP-712 rate quotes ───────────────────────────────────────────────────────
+// Signed off-chain by the quoter key under the "SettlX" v1 domain.
+sol! {
+    struct RateQuote {
+        uint256 paymentId;
+        bytes3 currency;
+        uint256 rate;
+        uint256 validUntil;
+        uint256 nonce;
+        address merchant;
+    }
+}
+
+// ── Payment view ──────────────────────────────────────────────────────────────
+// Returned by `get_payment_details` and `get_payments`.
+sol! {
+    struct PaymentDetails {
+        uint256 id;
+        address payer;
+        address merchant;
+        address token;
+        uint256 amount;             // authorized
+        uint256 capturedAmount;     // zero until accepted
+        uint256 refundedAmount;
+        uint256 fee;
+        uint256 escrowed;
+        bytes32 rfce;
+        uint8 status;
+        bytes3 currency;
+        uint256 lockedRate;
+        uint256 fiatAmount;         // settlement currency x 1e18
+        uint256 planId;
+        uint256 oracleRoundId;
+        uint256 createdAt;
+        uint256 expiresAt;
+        uint256 settlementDeadline;
+        uint256 updatedAt;          // last status change
+        uint256 acceptedAt;
+        uint256 rejectedAt;
+        uint256 paidAt;
+        uint256 cancelledAt;
+        uint256 expiredAt;
+        uint256 refundedAt;
+        uint256 disputedAt;
+        uint256 releasedAt;
+        uint256 voidedAt;
+        bytes32 settlementRef;
+    }
+}
+
+impl AbiType for PaymentDetails {
+    type SolType = Self;
+
+    // Named after the struct declared in the exported interface; never an argument,
+    // so it does not feed into any selector.
+    const ABI: ConstString = ConstString::new("PaymentDetails");
+
+    const EXPORT_ABI_RET: ConstString = ConstString::new("PaymentDetails memory");
+
+    const CAN_BE_CALLDATA: bool = false;
+}
+
+/// The exported struct declaration is read off the `sol!` definition, so the
+/// two cannot drift apart.
+#[cfg(feature = "export-abi")]
+impl stylus_sdk::abi::export::internal::InnerTypes for PaymentDetails {
+    fn inner_types() -> Vec<stylus_sdk::abi::export::internal::InnerType> {
+        // "PaymentDetails(uint256 id,address payer,...)" → "struct PaymentDetails { uint256 id; ... }"
+        let root = <Self as SolStruct>::eip712_root_type();
+        let fields = root.trim_start_matches("PaymentDetails(").trim_end_matches(')');
+        let body: String = fields.split(',').map(|field| format!(" {field};")).collect();
+        vec![stylus_sdk::abi::export::internal::InnerType {
+            name: format!("struct PaymentDetails {{{body} }}"),
+            id: core::any::TypeId::of::<Self>(),
+        }]
+    }
+}
+
 // ── Events ────────────────────────────────────────────────────────────────────
 sol! {
     event MerchantRegistered(
@@ -39,14 +312,101 @@ sol! {
         uint256 indexed id,
         address indexed payer,
         address indexed merchant,
+        address token,
         uint256 amount,
-        string rfce
+        string rfce,
+        uint256 expiresAt
     );
 
-    // lockedRate = NGN per USDC x 1e18
-    event PaymentAccepted(uint256 indexed id, uint256 lockedRate);
+    // lockedRate = units of `currency` (ISO 4217) per whole token x 1e18
+    event PaymentAccepted(uint256 indexed id, uint256 lockedRate, bytes3 currency, uint256 fee);
     event PaymentRejected(uint256 indexed id);
     event PaymentMarkedAsPaid(uint256 indexed id);
+
+    // Pending payment refunded after its expiry; reclaimedBy may be a keeper
+    event PaymentExpired(uint256 indexed id, address indexed reclaimedBy);
+    event PaymentCancelled(uint256 indexed id);
+    // Emitted alongside the step-specific event on every status change
+    event PaymentStatusChanged(uint256 indexed id, uint8 from, uint8 to, address indexed actor);
+    event PaymentCaptured(uint256 indexed id, uint256 capturedAmount, uint256 refundedAmount);
+    event PaymentVoided(uint256 indexed id, uint256 amount);
+    event PaymentRefunded(
+        uint256 indexed id,
+        address indexed refundedBy,
+        uint256 amount,
+        uint256 totalRefunded
+    );
+    event PaymentExpiryConfigUpdated(uint256 defaultTtl, uint256 maxTtl);
+
+    // evidenceHash points at off-chain evidence (e.g. an IPFS digest)
+    event DisputeOpened(
+        uint256 indexed id,
+        address indexed openedBy,
+        bytes32 evidenceHash,
+        uint256 deadline
+    );
+    event DisputeEvidenceSubmitted(
+        uint256 indexed id,
+        address indexed submittedBy,
+        bytes32 evidenceHash
+    );
+    event DisputeWithdrawn(uint256 indexed id);
+    // ruling: 1=RefundPayer 2=ForceSettle 3=CompensateMerchant
+    event DisputeResolved(uint256 indexed id, uint8 ruling, uint256 compensation);
+    event DisputePeriodUpdated(uint256 period);
+    event PaidDisputeWindowUpdated(uint256 window);
+
+    // Escrowed payment released straight to the merchant after the settlement deadline
+    event EscrowReleased(uint256 indexed id, address indexed merchant, uint256 amount);
+    event SettlementPeriodUpdated(uint256 period);
+
+    event RoleGranted(bytes32 indexed role, address indexed account, address indexed sender);
+    event RoleRevoked(bytes32 indexed role, address indexed account, address indexed sender);
+    event TreasuryUpdated(address indexed treasury);
+    event AdminTransferStarted(address indexed previousAdmin, address indexed newAdmin);
+    event AdminTransferCancelled(address indexed admin, address indexed pendingAdmin);
+    event AdminTransferred(address indexed previousAdmin, address indexed newAdmin);
+    event Initialized(uint64 version);
+
+    event UpgradeProposed(address indexed implementation, uint256 readyAt);
+    event UpgradeCancelled(address indexed implementation);
+    event Upgraded(address indexed implementation);
+    event UpgradeDelayUpdated(uint256 delay);
+
+    event MigrationStarted(address indexed legacy, address indexed token);
+    event PaymentImported(uint256 indexed id, uint8 status, uint256 escrowed);
+    event MerchantImported(address indexed merchant);
+    event MigrationSealed(address indexed legacy, uint256 importedPayments, uint256 nextPaymentId);
+
+    event TokenConfigured(address indexed token, uint8 decimals, bool enabled);
+    event ProtocolFeeUpdated(uint256 feeBps, address indexed recipient);
+    event TokenMinFeeUpdated(address indexed token, uint256 minFee);
+    event PricingPlanUpdated(
+        uint256 indexed planId,
+        uint256 feeBps,
+        uint256 fxSpreadBps,
+        uint256 minVolume,
+        bool active
+    );
+    event MerchantPlanAssigned(address indexed merchant, uint256 indexed planId);
+    event CurrencyConfigured(bytes3 indexed currency, bool enabled);
+    event MerchantCurrencyUpdated(address indexed merchant, bytes3 currency);
+    event CurrencyOracleUpdated(
+        bytes3 indexed currency,
+        address oracle,
+        uint256 maxDeviationBps,
+        uint256 maxStaleness
+    );
+    event SequencerFeedUpdated(address feed, uint256 gracePeriod);
+    event QuoterUpdated(address indexed quoter);
+    event RateQuotesRequiredUpdated(bool required);
+    event RateQuoteRedeemed(uint256 indexed id, address indexed merchant, uint256 nonce);
+
+    // flags: what this call changed; paused: the whole mask afterwards
+    event Paused(address indexed account, uint8 flags, uint8 paused);
+    event Unpaused(address indexed account, uint8 flags, uint8 paused);
+    event PauseGracePeriodUpdated(uint256 period);
+    event EmergencyWithdrawal(uint256 indexed id, address indexed payer, uint256 amount);
 }
 
 // ── Custom Errors ─────────────────────────────────────────────────────────────
@@ -54,15 +414,71 @@ sol! {
     error InvalidToken();
     error InvalidMerchant();
     error InvalidAmount();
-    error OnlyAdmin();
     error NotYourPayment();
-    error AlreadyProcessed();
     error InvalidRate();
     error BankNameRequired();
     error AccountNameRequired();
     error AccountNumberRequired();
-    error MustBeAcceptedFirst();
     error NotRegistered();
+    error TransferFailed(address token, address from, address to, uint256 amount);
+    error InvalidExpiry();
+    error PaymentHasExpired();
+    error PaymentNotExpired();
+    error DisputeAlreadyOpened();
+    error NoOpenDispute();
+    error DisputeDeadlinePassed();
+    error DisputeWindowClosed(uint256 closedAt);
+    error DisputeDeadlineNotReached(uint256 deadline);
+    error InvalidRuling();
+    error NotEscrowed();
+    error SettlementNotOverdue();
+    error TokenNotAccepted();
+    error InvalidDecimals();
+    error InvalidCurrency();
+    error CurrencyNotEnabled();
+    error RateOutOfBand(uint256 rate, uint256 oracleRate);
+    error StaleOracle();
+    error InvalidOraclePrice();
+    error SequencerDown();
+    error SequencerGracePeriodNotOver();
+    error InvalidOracleConfig();
+    error InvalidSignature();
+    error QuoteExpired();
+    error QuoteAlreadyUsed();
+    error QuoteNotForPayment(uint256 id);
+    error QuoteRequired();
+    error MissingRole(bytes32 role, address account);
+    error TreasuryRoleInUse();
+    error OnlyAdmin();
+    error InvalidAdmin();
+    error NotPendingAdmin();
+    error EnforcedPause(uint8 flag);
+    error InvalidPauseFlags();
+    error NotPaused();
+    error PauseGracePeriodNotOver();
+    error AlreadyInitialized();
+    error InvalidInitialization();
+    error NotProxied();
+    error InvalidImplementation();
+    error NoPendingUpgrade();
+    error UpgradeNotReady(uint256 readyAt);
+    error InvalidUpgradeDelay();
+    error MigrationInProgress();
+    error InvalidMigrationState();
+    error LengthMismatch();
+    error LegacyPaymentNotFound(uint256 id);
+    error PaymentAlreadyImported(uint256 id);
+    error LegacyPaymentHeld(uint256 id);
+    error LegacyMerchantNotFound(address merchant);
+    error MerchantAlreadyImported(address merchant);
+    error InvalidFee();
+    error FeeExceedsAmount(uint256 fee, uint256 amount);
+    error InvalidPlan();
+    error VolumeThresholdNotMet(uint256 volume, uint256 minVolume);
+    error BatchTooLarge(uint256 size, uint256 maxSize);
+    error InvalidTransition(uint8 from, uint8 to);
+    error PaymentNotFound(uint256 id);
+    error PaymentDisputed(uint256 id);
 }
 
 #[derive(SolidityError)]
@@ -70,29 +486,122 @@ pub enum SettlXError {
     InvalidToken(InvalidToken),
     InvalidMerchant(InvalidMerchant),
     InvalidAmount(InvalidAmount),
-    OnlyAdmin(OnlyAdmin),
     NotYourPayment(NotYourPayment),
-    AlreadyProcessed(AlreadyProcessed),
     InvalidRate(InvalidRate),
     BankNameRequired(BankNameRequired),
     AccountNameRequired(AccountNameRequired),
     AccountNumberRequired(AccountNumberRequired),
-    MustBeAcceptedFirst(MustBeAcceptedFirst),
     NotRegistered(NotRegistered),
+    TransferFailed(TransferFailed),
+    InvalidExpiry(InvalidExpiry),
+    PaymentHasExpired(PaymentHasExpired),
+    PaymentNotExpired(PaymentNotExpired),
+    DisputeAlreadyOpened(DisputeAlreadyOpened),
+    NoOpenDispute(NoOpenDispute),
+    DisputeDeadlinePassed(DisputeDeadlinePassed),
+    DisputeWindowClosed(DisputeWindowClosed),
+    DisputeDeadlineNotReached(DisputeDeadlineNotReached),
+    InvalidRuling(InvalidRuling),
+    NotEscrowed(NotEscrowed),
+    SettlementNotOverdue(SettlementNotOverdue),
+    TokenNotAccepted(TokenNotAccepted),
+    InvalidDecimals(InvalidDecimals),
+    InvalidCurrency(InvalidCurrency),
+    CurrencyNotEnabled(CurrencyNotEnabled),
+    RateOutOfBand(RateOutOfBand),
+    StaleOracle(StaleOracle),
+    InvalidOraclePrice(InvalidOraclePrice),
+    SequencerDown(SequencerDown),
+    SequencerGracePeriodNotOver(SequencerGracePeriodNotOver),
+    InvalidOracleConfig(InvalidOracleConfig),
+    InvalidSignature(InvalidSignature),
+    QuoteExpired(QuoteExpired),
+    QuoteAlreadyUsed(QuoteAlreadyUsed),
+    QuoteNotForPayment(QuoteNotForPayment),
+    QuoteRequired(QuoteRequired),
+    MissingRole(MissingRole),
+    TreasuryRoleInUse(TreasuryRoleInUse),
+    OnlyAdmin(OnlyAdmin),
+    InvalidAdmin(InvalidAdmin),
+    NotPendingAdmin(NotPendingAdmin),
+    EnforcedPause(EnforcedPause),
+    InvalidPauseFlags(InvalidPauseFlags),
+    NotPaused(NotPaused),
+    PauseGracePeriodNotOver(PauseGracePeriodNotOver),
+    AlreadyInitialized(AlreadyInitialized),
+    InvalidInitialization(InvalidInitialization),
+    NotProxied(NotProxied),
+    InvalidImplementation(InvalidImplementation),
+    NoPendingUpgrade(NoPendingUpgrade),
+    UpgradeNotReady(UpgradeNotReady),
+    InvalidUpgradeDelay(InvalidUpgradeDelay),
+    MigrationInProgress(MigrationInProgress),
+    InvalidMigrationState(InvalidMigrationState),
+    LengthMismatch(LengthMismatch),
+    LegacyPaymentNotFound(LegacyPaymentNotFound),
+    PaymentAlreadyImported(PaymentAlreadyImported),
+    LegacyPaymentHeld(LegacyPaymentHeld),
+    LegacyMerchantNotFound(LegacyMerchantNotFound),
+    MerchantAlreadyImported(MerchantAlreadyImported),
+    InvalidFee(InvalidFee),
+    FeeExceedsAmount(FeeExceedsAmount),
+    InvalidPlan(InvalidPlan),
+    VolumeThresholdNotMet(VolumeThresholdNotMet),
+    BatchTooLarge(BatchTooLarge),
+    InvalidTransition(InvalidTransition),
+    PaymentNotFound(PaymentNotFound),
+    PaymentDisputed(PaymentDisputed),
 }
 
 // ── Storage Layout ────────────────────────────────────────────────────────────
+// Storage lives in the proxy and outlives every implementation, so the layout
+// is append-only: never reorder, remove or retype a field, in SettlX or in any
+// struct kept in a mapping. New fields go at the end of their struct and read
+// as zero on existing records. If they need a non-zero starting value, bump
+// VERSION and backfill it in `reinitialize`.
 sol_storage! {
     #[derive(Erase)]
     pub struct Payment {
         uint256 id;
         address payer;
         address merchant;
-        uint256 amount;
+        uint256 amount;      // in `token` units
         uint256 timestamp;
         bytes32 rfce;        // keccak256 of reference string; plaintext only in PaymentCreated event
-        uint8 status;        // 0=Pending 1=Accepted 2=Rejected 3=Paid
-        uint256 locked_rate; // NGN per USDC x 1e18, set on acceptance
+        uint8 status;        // 0=Pending 1=Accepted 2=Rejected 3=Paid 4=Expired 5=Cancelled
+                             // 6=Disputed 7=Refunded 8=Released 9=Voided
+                             // (emergency withdrawals also end in 5=Cancelled)
+                             // see PaymentStatus for the allowed transitions
+        uint256 locked_rate; // `currency` per whole token x 1e18, set on acceptance
+        uint256 expires_at;  // after this, anyone can refund a still-Pending payment
+        uint256 escrowed;    // tokens still held by the contract after acceptance (escrow mode)
+        uint256 settlement_deadline; // escrow can be released to the merchant after this
+        address token;       // stablecoin the payer escrowed
+        bytes3 currency;     // ISO 4217 settlement currency, set on acceptance
+        uint256 oracle_round_id; // feed round the locked rate was checked against (0 = no feed)
+        uint256 fee;         // protocol fee taken at acceptance, in `token` units
+        uint256 plan_id;     // pricing plan applied at acceptance (0 = default terms)
+        uint256 captured_amount; // part of `amount` taken at acceptance; 0 on older records = all of it
+        uint256 refunded_amount; // everything returned to the payer so far
+        uint256 updated_at;  // last status change (creation time until then)
+        address updated_by;  // caller behind the last status change
+        // First time the payment reached each milestone; zero if it never did.
+        // Imported payments start with none of these.
+        uint256 accepted_at;
+        address accepted_by;
+        uint256 rejected_at;
+        address rejected_by;
+        uint256 paid_at;
+        address paid_by;
+        bytes32 settlement_ref; // settler's off-chain payout reference (0 = none given)
+        bool legacy_held;       // imported while Pending; the legacy contract can still settle it
+        // More milestones, on the same first-arrival rule as accepted_at.
+        uint256 cancelled_at;   // includes emergency withdrawals
+        uint256 expired_at;
+        uint256 refunded_at;    // first refund; later partial refunds keep it
+        uint256 disputed_at;
+        uint256 released_at;
+        uint256 voided_at;
     }
 
     /// MerchantInfo derives Erase so individual fields can be erased before
@@ -103,17 +612,115 @@ sol_storage! {
         bytes32 account_name;
         bytes32 account_number;
         bool is_registered;
+        bytes3 settlement_currency; // preferred ISO 4217 code; zero = contract default
+        uint256 plan_id;            // pricing plan; 0 = default terms
+        uint256 volume;             // accepted minus refunded volume, normalised to 18 decimals
+    }
+
+    /// Admin-defined merchant terms. Plan 0 is implicit: the global protocol
+    /// fee and no FX spread.
+    #[derive(Erase)]
+    pub struct PricingPlan {
+        bool listed;
+        bool active;
+        uint256 fee_bps;        // replaces the global protocol fee
+        uint256 fx_spread_bps;  // taken off the merchant's rate before it is locked
+        uint256 min_volume;     // accepted volume (18 decimals) needed to claim the plan
+    }
+
+    /// Admin allowlist entry for a stablecoin. Decimals are fixed once listed
+    /// so existing payments keep normalising the same way.
+    #[derive(Erase)]
+    pub struct TokenConfig {
+        bool listed;
+        bool enabled;
+        uint8 decimals;
+        uint256 min_fee;    // floor on the protocol fee, in the token's own units
+    }
+
+    /// Admin allowlist entry for a fiat settlement currency. When `oracle` is
+    /// set, locked rates must sit within `max_deviation_bps` of its answer.
+    #[derive(Erase)]
+    pub struct CurrencyConfig {
+        bool listed;
+        bool enabled;
+        address oracle;             // feed answering `currency` per USD
+        uint256 max_deviation_bps;
+        uint256 max_staleness;      // seconds since the feed's last update
+    }
+
+    /// One dispute per payment, keyed by payment id.
+    #[derive(Erase)]
+    pub struct Dispute {
+        address opened_by;
+        bytes32 evidence_hash;  // latest evidence; history lives in the events
+        uint256 opened_at;
+        uint256 deadline;       // no new evidence after this
+        uint8 state;            // 0=None 1=Open 2=Resolved 3=Withdrawn
+        uint8 ruling;           // 0=None 1=RefundPayer 2=ForceSettle 3=CompensateMerchant
+        uint8 previous_status;  // payment status restored if the dispute is withdrawn
+        uint256 compensation;   // paid to the merchant on CompensateMerchant, in the payment token
+        uint256 resolved_at;
+        bool payer_submitted;    // payer has opened or added evidence
+        bool merchant_submitted; // merchant has opened or added evidence
+    }
+
+    /// Enumerable set of payment ids: O(1) insert, and swap-and-pop removal,
+    /// so ids are not kept in any particular order.
+    pub struct PaymentSet {
+        uint256[] ids;
+        mapping(uint256 => uint256) positions; // index in `ids` + 1; 0 = absent
     }
 
     #[entrypoint]
     pub struct SettlX {
-        address stable_token;
+        address stable_token;   // token listed at init; others are added via set_accepted_token
         uint256 next_payment_id;
-        address admin;
+        address admin;          // holds every role the deployer started with; rotated via transfer_admin
         mapping(uint256 => Payment) payments;
         mapping(address => uint256[]) merchant_payments;
         mapping(address => MerchantInfo) merchants;
         mapping(address => uint256[]) payer_payments;
+        uint256 default_payment_ttl;
+        uint256 max_payment_ttl;
+        uint256 dispute_period;
+        mapping(uint256 => Dispute) disputes;
+        bool escrow_until_paid;
+        uint256 settlement_period;
+        mapping(address => TokenConfig) tokens;
+        address[] token_list;
+        mapping(bytes3 => CurrencyConfig) currencies;
+        bytes3[] currency_list;
+        address sequencer_uptime_feed; // zero on chains without a sequencer feed
+        uint256 sequencer_grace_period;
+        address quoter;                // signs EIP-712 RateQuotes
+        bool require_rate_quotes;      // when set, only accept_payment_with_quote can lock rates
+        mapping(address => mapping(uint256 => bool)) used_quote_nonces;
+        mapping(bytes32 => mapping(address => bool)) roles;
+        address treasury;              // receives accepted funds; must hold TREASURY_ROLE
+        address pending_admin;         // nominated by transfer_admin, confirmed by accept_admin
+        uint8 paused;                  // PAUSE_* bitmask
+        uint256 paused_since;          // when `paused` last went from zero to non-zero
+        uint256 pause_grace_period;    // pause length after which emergency_withdraw opens
+        uint64 initialized_version;    // 0 = never initialised; bumped by reinitialize
+        uint256 upgrade_delay;
+        address pending_implementation; // proposed by propose_upgrade
+        uint256 upgrade_ready_at;       // execute_upgrade allowed from this time
+        address legacy_contract;        // old deployment being imported from
+        address legacy_token;           // the old deployment's stable_token
+        uint8 migration_state;          // 0=None 1=Active 2=Sealed
+        uint256 imported_payments;
+        uint256 fee_bps;                // protocol fee on accepted amounts
+        address fee_recipient;          // zero = no fee is charged
+        mapping(address => uint256) total_fees_collected; // per token
+        mapping(uint256 => PricingPlan) pricing_plans;
+        uint256[] pricing_plan_ids;
+        // Open-payment queues, kept in step with `Payment.status` by set_status.
+        PaymentSet pending_payments;              // status 0
+        PaymentSet awaiting_settlement;           // status 1
+        mapping(address => PaymentSet) merchant_pending;
+        mapping(address => PaymentSet) merchant_awaiting_settlement;
+        uint256 paid_dispute_window;    // Paid payments can be disputed this long after paid_at
     }
 }
 
@@ -121,278 +728,2929 @@ sol_storage! {
 impl SettlX {
     // ── Initialisation ────────────────────────────────────────────────────────
 
-    pub fn init(&mut self, token_address: Address) -> Result<(), SettlXError> {
-        if token_address == Address::ZERO {
-            return Err(SettlXError::InvalidToken(InvalidToken {}));
+    /// Runs once, atomically with deployment. `token_address` becomes the first
+    /// accepted stablecoin, with `token_decimals`. `escrow_until_paid` picks
+    /// the settlement mode for this deployment: when false, accepted funds go
+    /// to the treasury immediately; when true, they stay in the contract until
+    /// `mark_as_paid` releases them. `admin` receives every role and becomes
+    /// the initial treasury; it is named explicitly because neither the
+    /// deployer factory (msg.sender) nor the submitting key (tx.origin) need be
+    /// the intended owner, e.g. when a multisig deploys.
+    #[constructor]
+    pub fn constructor(
+        &mut self,
+        admin: Address,
+        token_address: Address,
+        token_decimals: u8,
+        escrow_until_paid: bool,
+    ) -> Result<(), SettlXError> {
+        if admin == Address::ZERO {
+            return Err(SettlXError::InvalidAdmin(InvalidAdmin {}));
         }
-        self.stable_token.set(token_address);
-        self.next_payment_id.set(U256::from(1));
-        self.admin.set(self.vm().msg_sender());
-        Ok(())
+        self.initialize(admin, token_address, token_decimals, escrow_until_paid)
     }
 
-    // ── Merchant Registration ─────────────────────────────────────────────────
-
-    /// Register bank details for the first time.
-    /// Stores keccak256 hashes on-chain; plaintext emitted in MerchantRegistered event.
-    pub fn register_merchant_bank_details(
+    /// Same setup as the constructor, for deployments where the constructor
+    /// never runs against this storage (e.g. behind a proxy). Succeeds only
+    /// once; the caller receives every role and becomes the initial treasury.
+    pub fn init(
         &mut self,
-        bank_name: String,
-        account_name: String,
-        account_number: String,
+        token_address: Address,
+        token_decimals: u8,
+        escrow_until_paid: bool,
     ) -> Result<(), SettlXError> {
-        if bank_name.is_empty() {
-            return Err(SettlXError::BankNameRequired(BankNameRequired {}));
+        let admin = self.vm().msg_sender();
+        self.initialize(admin, token_address, token_decimals, escrow_until_paid)
+    }
+
+    /// Bring storage written by an older implementation up to `version`, at
+    /// most this build's VERSION. Each release that adds fields needing a
+    /// non-zero value, or rewrites parameters in place, adds its steps here
+    /// keyed on the version that introduced them; versions only move forward.
+    pub fn reinitialize(&mut self, version: u64) -> Result<(), SettlXError> {
+        self.only_role(DEFAULT_ADMIN_ROLE)?;
+        let from = self.initialized_version.get().to::<u64>();
+        if version <= from || version > VERSION {
+            return Err(SettlXError::InvalidInitialization(InvalidInitialization {}));
         }
-        if account_name.is_empty() {
-            return Err(SettlXError::AccountNameRequired(AccountNameRequired {}));
+
+        // 2: deployments from before the upgrade timelock.
+        if from < 2 && version >= 2 && self.upgrade_delay.get() == U256::ZERO {
+            self.upgrade_delay.set(U256::from(DEFAULT_UPGRADE_DELAY));
         }
-        if account_number.is_empty() {
-            return Err(SettlXError::AccountNumberRequired(AccountNumberRequired {}));
+        // 3: Paid payments could be disputed at any time.
+        if from < 3 && version >= 3 {
+            self.paid_dispute_window.set(U256::from(DEFAULT_PAID_DISPUTE_WINDOW));
         }
 
-        let sender = self.vm().msg_sender();
-        let mut merchant = self.merchants.setter(sender);
+        self.initialized_version.set(U64::from(version));
+        log(self.vm(), Initialized { version });
+        Ok(())
+    }
 
-        merchant.bank_name.set(keccak256(bank_name.as_bytes()));
-        merchant.account_name.set(keccak256(account_name.as_bytes()));
-        merchant.account_number.set(keccak256(account_number.as_bytes()));
-        merchant.is_registered.set(true);
+    // ── Access Control ────────────────────────────────────────────────────────
 
-        log(self.vm(), MerchantRegistered {
-            merchant: sender,
-            bankName: bank_name,
-            accountName: account_name,
-            accountNumber: account_number,
-        });
+    pub fn grant_role(&mut self, role: FixedBytes<32>, account: Address) -> Result<(), SettlXError> {
+        self.only_role(DEFAULT_ADMIN_ROLE)?;
+        self.grant(role, account);
+        Ok(())
+    }
 
+    pub fn revoke_role(&mut self, role: FixedBytes<32>, account: Address) -> Result<(), SettlXError> {
+        self.only_role(DEFAULT_ADMIN_ROLE)?;
+        self.revoke(role, account)
+    }
+
+    /// Give up one of the caller's own roles.
+    pub fn renounce_role(&mut self, role: FixedBytes<32>) -> Result<(), SettlXError> {
+        let sender = self.vm().msg_sender();
+        self.revoke(role, sender)
+    }
+
+    /// Point accepted-payment funds at `treasury`, which must hold TREASURY_ROLE.
+    pub fn set_treasury(&mut self, treasury: Address) -> Result<(), SettlXError> {
+        self.only_role(DEFAULT_ADMIN_ROLE)?;
+        if !self.roles.get(TREASURY_ROLE).get(treasury) {
+            return Err(SettlXError::MissingRole(MissingRole {
+                role: TREASURY_ROLE,
+                account: treasury,
+            }));
+        }
+        self.treasury.set(treasury);
+        log(self.vm(), TreasuryUpdated { treasury });
         Ok(())
     }
 
-    /// Update bank details for an already-registered merchant.
-    ///
-    /// WHY erase() BEFORE set():
-    /// Stylus bytes32 storage slots must be fully zeroed before writing a new value.
-    /// Without erase(), the second+ update silently corrupts the slot because the
-    /// old non-zero bits are OR'd with the new value instead of being replaced.
-    /// erase() zeroes the slot first, making set() work correctly every time.
-    pub fn update_merchant_bank_details(
-        &mut self,
-        bank_name: String,
-        account_name: String,
-        account_number: String,
-    ) -> Result<(), SettlXError> {
-        if bank_name.is_empty() {
-            return Err(SettlXError::BankNameRequired(BankNameRequired {}));
+    /// Nominate `new_admin`. Nothing moves until they call `accept_admin`;
+    /// nominating again replaces the pending address.
+    pub fn transfer_admin(&mut self, new_admin: Address) -> Result<(), SettlXError> {
+        let admin = self.admin.get();
+        if self.vm().msg_sender() != admin {
+            return Err(SettlXError::OnlyAdmin(OnlyAdmin {}));
         }
-        if account_name.is_empty() {
-            return Err(SettlXError::AccountNameRequired(AccountNameRequired {}));
+        if new_admin == Address::ZERO || new_admin == admin {
+            return Err(SettlXError::InvalidAdmin(InvalidAdmin {}));
         }
-        if account_number.is_empty() {
-            return Err(SettlXError::AccountNumberRequired(AccountNumberRequired {}));
+        self.pending_admin.set(new_admin);
+        log(self.vm(), AdminTransferStarted { previousAdmin: admin, newAdmin: new_admin });
+        Ok(())
+    }
+
+    pub fn cancel_admin_transfer(&mut self) -> Result<(), SettlXError> {
+        let admin = self.admin.get();
+        if self.vm().msg_sender() != admin {
+            return Err(SettlXError::OnlyAdmin(OnlyAdmin {}));
         }
+        let pending_admin = self.pending_admin.get();
+        if pending_admin == Address::ZERO {
+            return Err(SettlXError::NotPendingAdmin(NotPendingAdmin {}));
+        }
+        self.pending_admin.set(Address::ZERO);
+        log(self.vm(), AdminTransferCancelled { admin, pendingAdmin: pending_admin });
+        Ok(())
+    }
 
+    /// Complete the handover. Every role the old admin still holds moves to
+    /// the caller, and if the old admin was the treasury, accepted funds are
+    /// redirected only now — never while the handover is half-finished.
+    pub fn accept_admin(&mut self) -> Result<(), SettlXError> {
         let sender = self.vm().msg_sender();
-        let mut merchant = self.merchants.setter(sender);
-
-        if !merchant.is_registered.get() {
-            return Err(SettlXError::NotRegistered(NotRegistered {}));
+        if sender == Address::ZERO || sender != self.pending_admin.get() {
+            return Err(SettlXError::NotPendingAdmin(NotPendingAdmin {}));
         }
+        let previous = self.admin.get();
 
-        // Erase each slot before writing — required for correct bytes32 overwrite
-        // in Stylus. Skipping erase() causes corruption on the 3rd+ update.
-        merchant.bank_name.erase();
-        merchant.bank_name.set(keccak256(bank_name.as_bytes()));
-
-        merchant.account_name.erase();
-        merchant.account_name.set(keccak256(account_name.as_bytes()));
+        if self.treasury.get() == previous {
+            self.grant(TREASURY_ROLE, sender);
+            self.treasury.set(sender);
+            log(self.vm(), TreasuryUpdated { treasury: sender });
+        }
+        for role in ALL_ROLES {
+            if self.roles.get(role).get(previous) {
+                self.grant(role, sender);
+                self.revoke(role, previous)?;
+            }
+        }
 
-        merchant.account_number.erase();
-        merchant.account_number.set(keccak256(account_number.as_bytes()));
+        self.admin.set(sender);
+        self.pending_admin.set(Address::ZERO);
 
-        log(self.vm(), MerchantUpdated {
-            merchant: sender,
-            bankName: bank_name,
-            accountName: account_name,
-            accountNumber: account_number,
-        });
+        log(self.vm(), AdminTransferred { previousAdmin: previous, newAdmin: sender });
 
         Ok(())
     }
 
-    // ── Payment Lifecycle ─────────────────────────────────────────────────────
+    // ── Emergency Pause ───────────────────────────────────────────────────────
 
-    pub fn pay_merchant(
-        &mut self,
-        merchant: Address,
-        amount: U256,
-        rfce: String,
-    ) -> Result<(), SettlXError> {
-        if merchant == Address::ZERO {
-            return Err(SettlXError::InvalidMerchant(InvalidMerchant {}));
+    /// Halt the entrypoints behind `flags` (any mix of PAUSE_* bits). Adding
+    /// flags to an existing pause does not restart the grace period.
+    pub fn pause(&mut self, flags: u8) -> Result<(), SettlXError> {
+        self.only_role(PAUSER_ROLE)?;
+        if flags == 0 || flags & !PAUSE_ALL != 0 {
+            return Err(SettlXError::InvalidPauseFlags(InvalidPauseFlags {}));
         }
-        if amount == U256::ZERO {
-            return Err(SettlXError::InvalidAmount(InvalidAmount {}));
+        let current = self.paused.get().to::<u8>();
+        if current == 0 {
+            let now = U256::from(self.vm().block_timestamp());
+            self.paused_since.set(now);
         }
+        let paused = current | flags;
+        self.paused.set(U8::from(paused));
+        log(self.vm(), Paused {
+            account: self.vm().msg_sender(),
+            flags: flags & !current,
+            paused,
+        });
+        Ok(())
+    }
 
-        let payer = self.vm().msg_sender();
-        let contract_addr = self.vm().contract_address();
-        let current_time = self.vm().block_timestamp();
-        let stable_token = self.stable_token.get();
-
-        let token = IERC20::new(stable_token);
-        let _ = token.transfer_from(&mut *self, payer, contract_addr, amount);
-
-        let id = self.next_payment_id.get();
-        let mut payment = self.payments.setter(id);
-        payment.id.set(id);
-        payment.payer.set(payer);
-        payment.merchant.set(merchant);
-        payment.amount.set(amount);
-        payment.timestamp.set(U256::from(current_time));
-        payment.rfce.set(keccak256(rfce.as_bytes()));
-        payment.status.set(U8::from(0u8));
-        payment.locked_rate.set(U256::ZERO);
-
-        self.merchant_payments.setter(merchant).push(id);
-        self.payer_payments.setter(payer).push(id);
-        self.next_payment_id.set(id + U256::from(1));
-
-        log(self.vm(), PaymentCreated { id, payer, merchant, amount, rfce });
+    /// Resume the entrypoints behind `flags`. The grace period clock only
+    /// resets once nothing is paused.
+    pub fn unpause(&mut self, flags: u8) -> Result<(), SettlXError> {
+        self.only_role(PAUSER_ROLE)?;
+        if flags == 0 || flags & !PAUSE_ALL != 0 {
+            return Err(SettlXError::InvalidPauseFlags(InvalidPauseFlags {}));
+        }
+        let current = self.paused.get().to::<u8>();
+        let paused = current & !flags;
+        self.paused.set(U8::from(paused));
+        if paused == 0 {
+            self.paused_since.set(U256::ZERO);
+        }
+        log(self.vm(), Unpaused {
+            account: self.vm().msg_sender(),
+            flags: current & flags,
+            paused,
+        });
+        Ok(())
+    }
 
+    /// Set how long, in seconds, a pause must last before `emergency_withdraw` opens.
+    pub fn set_pause_grace_period(&mut self, period: U256) -> Result<(), SettlXError> {
+        self.only_role(DEFAULT_ADMIN_ROLE)?;
+        self.pause_grace_period.set(period);
+        log(self.vm(), PauseGracePeriodUpdated { period });
         Ok(())
     }
 
-    pub fn accept_payment_with_rate(
-        &mut self,
-        payment_id: U256,
-        rate: U256,
-    ) -> Result<(), SettlXError> {
+    /// Payer escape hatch: once the contract has been paused for longer than
+    /// the grace period, the payer can pull a Pending payment back regardless
+    /// of which flags are set. Never paused itself.
+    pub fn emergency_withdraw(&mut self, payment_id: U256) -> Result<(), SettlXError> {
+        if self.paused.get().to::<u8>() == 0 {
+            return Err(SettlXError::NotPaused(NotPaused {}));
+        }
+        let now = U256::from(self.vm().block_timestamp());
+        if now <= self.paused_since.get().saturating_add(self.pause_grace_period.get()) {
+            return Err(SettlXError::PauseGracePeriodNotOver(PauseGracePeriodNotOver {}));
+        }
+
         let sender = self.vm().msg_sender();
-        let mut payment = self.payments.setter(payment_id);
+        let payment = self.payments.get(payment_id);
 
-        if payment.merchant.get() != sender {
+        if payment.payer.get() != sender {
             return Err(SettlXError::NotYourPayment(NotYourPayment {}));
         }
-        if payment.status.get().to::<u8>() != 0u8 {
-            return Err(SettlXError::AlreadyProcessed(AlreadyProcessed {}));
-        }
-        if rate == U256::ZERO {
-            return Err(SettlXError::InvalidRate(InvalidRate {}));
-        }
+        check_transition(payment.status.get(), PaymentStatus::Cancelled)?;
 
-        payment.status.set(U8::from(1u8));
-        payment.locked_rate.set(rate);
-
-        let admin = self.admin.get();
         let amount = payment.amount.get();
-        let stable_token = self.stable_token.get();
+        let token = payment.token.get();
         drop(payment);
+        self.set_status(payment_id, PaymentStatus::Cancelled)?;
+        self.payments.setter(payment_id).refunded_amount.set(amount);
 
-        let token = IERC20::new(stable_token);
-        let _ = token.transfer(&mut *self, admin, amount);
+        self.safe_transfer(token, sender, amount)?;
 
-        log(self.vm(), PaymentAccepted { id: payment_id, lockedRate: rate });
+        log(self.vm(), EmergencyWithdrawal { id: payment_id, payer: sender, amount });
 
         Ok(())
     }
 
-    pub fn reject_payment(&mut self, payment_id: U256) -> Result<(), SettlXError> {
-        let sender = self.vm().msg_sender();
-        let mut payment = self.payments.setter(payment_id);
+    // ── Upgrades ──────────────────────────────────────────────────────────────
 
-        if payment.merchant.get() != sender {
-            return Err(SettlXError::NotYourPayment(NotYourPayment {}));
+    /// Queue `implementation` to replace this one once the upgrade delay has
+    /// passed. Proposing again replaces the queued implementation and restarts
+    /// the clock.
+    pub fn propose_upgrade(&mut self, implementation: Address) -> Result<(), SettlXError> {
+        self.only_role(UPGRADER_ROLE)?;
+        if self.implementation() == Address::ZERO {
+            return Err(SettlXError::NotProxied(NotProxied {}));
         }
-        if payment.status.get().to::<u8>() != 0u8 {
-            return Err(SettlXError::AlreadyProcessed(AlreadyProcessed {}));
-        }
-
-        payment.status.set(U8::from(2u8));
-
-        let payer = payment.payer.get();
-        let amount = payment.amount.get();
-        let stable_token = self.stable_token.get();
-        drop(payment);
+        self.check_proxiable(implementation)?;
 
-        let token = IERC20::new(stable_token);
-        let _ = token.transfer(&mut *self, payer, amount);
+        let now = U256::from(self.vm().block_timestamp());
+        let ready_at = now.saturating_add(self.upgrade_delay.get());
+        self.pending_implementation.set(implementation);
+        self.upgrade_ready_at.set(ready_at);
 
-        log(self.vm(), PaymentRejected { id: payment_id });
+        log(self.vm(), UpgradeProposed { implementation, readyAt: ready_at });
 
         Ok(())
     }
 
-    pub fn mark_as_paid(&mut self, payment_id: U256) -> Result<(), SettlXError> {
-        if self.vm().msg_sender() != self.admin.get() {
-            return Err(SettlXError::OnlyAdmin(OnlyAdmin {}));
+    pub fn cancel_upgrade(&mut self) -> Result<(), SettlXError> {
+        self.only_role(UPGRADER_ROLE)?;
+        let implementation = self.pending_implementation.get();
+        if implementation == Address::ZERO {
+            return Err(SettlXError::NoPendingUpgrade(NoPendingUpgrade {}));
         }
+        self.pending_implementation.set(Address::ZERO);
+        self.upgrade_ready_at.set(U256::ZERO);
+        log(self.vm(), UpgradeCancelled { implementation });
+        Ok(())
+    }
 
-        let mut payment = self.payments.setter(payment_id);
+    /// Point the proxy at the queued implementation. If the new build has a
+    /// higher VERSION, call `reinitialize` on it next to run its backfills.
+    pub fn execute_upgrade(&mut self) -> Result<(), SettlXError> {
+        self.only_role(UPGRADER_ROLE)?;
+        if self.implementation() == Address::ZERO {
+            return Err(SettlXError::NotProxied(NotProxied {}));
+        }
+        let implementation = self.pending_implementation.get();
+        if implementation == Address::ZERO {
+            return Err(SettlXError::NoPendingUpgrade(NoPendingUpgrade {}));
+        }
+        let ready_at = self.upgrade_ready_at.get();
+        if U256::from(self.vm().block_timestamp()) < ready_at {
+            return Err(SettlXError::UpgradeNotReady(UpgradeNotReady { readyAt: ready_at }));
+        }
+        self.check_proxiable(implementation)?;
+
+        self.pending_implementation.set(Address::ZERO);
+        self.upgrade_ready_at.set(U256::ZERO);
+        // SAFETY: the ERC-1967 slot is a keccak-derived slot far away from
+        // anything `sol_storage!` lays out, so no typed field aliases it.
+        unsafe {
+            self.vm().storage_cache_bytes32(
+                U256::from_be_bytes(IMPLEMENTATION_SLOT.0),
+                implementation.into_word(),
+            );
+        }
+        self.vm().flush_cache(false);
+
+        log(self.vm(), Upgraded { implementation });
+
+        Ok(())
+    }
+
+    /// Set the upgrade timelock, in seconds. Cannot go below one day.
+    pub fn set_upgrade_delay(&mut self, delay: U256) -> Result<(), SettlXError> {
+        self.only_role(DEFAULT_ADMIN_ROLE)?;
+        if delay < U256::from(MIN_UPGRADE_DELAY) {
+            return Err(SettlXError::InvalidUpgradeDelay(InvalidUpgradeDelay {}));
+        }
+        self.upgrade_delay.set(delay);
+        log(self.vm(), UpgradeDelayUpdated { delay });
+        Ok(())
+    }
+
+    /// ERC-1822: the slot this implementation writes upgrades to.
+    #[selector(name = "proxiableUUID")]
+    pub fn proxiable_uuid(&self) -> FixedBytes<32> {
+        IMPLEMENTATION_SLOT
+    }
+
+    // ── Legacy Migration ──────────────────────────────────────────────────────
+    // Imports a deployment built from legacy/set-leg.rs. While a migration is
+    // active, payments, acceptance, settlement, merchant registration and
+    // disputes are blocked; sealing the migration opens the contract.
+
+    /// Begin importing from `legacy`, whose payments were all in `token`.
+    /// Only possible on a fresh contract with no payments of its own.
+    pub fn start_migration(&mut self, legacy: Address, token: Address) -> Result<(), SettlXError> {
+        self.only_role(DEFAULT_ADMIN_ROLE)?;
+        if self.migration_state.get().to::<u8>() != MIGRATION_NONE
+            || self.next_payment_id.get() != U256::from(1)
+        {
+            return Err(SettlXError::InvalidMigrationState(InvalidMigrationState {}));
+        }
+        if legacy == Address::ZERO || self.vm().code_size(legacy) == 0 {
+            return Err(SettlXError::InvalidMigrationState(InvalidMigrationState {}));
+        }
+        if !self.tokens.get(token).listed.get() {
+            return Err(SettlXError::TokenNotAccepted(TokenNotAccepted {}));
+        }
+
+        self.legacy_contract.set(legacy);
+        self.legacy_token.set(token);
+        self.migration_state.set(U8::from(MIGRATION_ACTIVE));
+
+        log(self.vm(), MigrationStarted { legacy, token });
+
+        Ok(())
+    }
+
+    /// Copy `ids` from the legacy contract under the same ids, along with
+    /// their merchant and payer index entries. The legacy contract has no
+    /// locked-rate getter, so `locked_rates[i]` (taken from its
+    /// PaymentAccepted events) must be non-zero exactly for Accepted and
+    /// Paid payments.
+    ///
+    /// Pending payments get a fresh default expiry. Their escrow stays locked
+    /// in the legacy contract, so the caller funds it here with a transfer
+    /// of the total Pending amount (needs an allowance in the legacy token).
+    /// The legacy contract cannot be frozen, so imported Pending payments are
+    /// held: they cannot leave Pending until the migration is sealed, and
+    /// then only while the legacy contract still reports them as Pending.
+    pub fn import_payments(
+        &mut self,
+        ids: Vec<U256>,
+        locked_rates: Vec<U256>,
+    ) -> Result<(), SettlXError> {
+        self.only_role(DEFAULT_ADMIN_ROLE)?;
+        if self.migration_state.get().to::<u8>() != MIGRATION_ACTIVE {
+            return Err(SettlXError::InvalidMigrationState(InvalidMigrationState {}));
+        }
+        check_batch_size(ids.len())?;
+        if ids.len() != locked_rates.len() {
+            return Err(SettlXError::LengthMismatch(LengthMismatch {}));
+        }
+
+        let legacy = self.legacy_contract.get();
+        let token = self.legacy_token.get();
+        let now = U256::from(self.vm().block_timestamp());
+        let expires_at = now.saturating_add(self.default_payment_ttl.get());
+        let mut escrow_total = U256::ZERO;
+
+        for (&id, &rate) in ids.iter().zip(locked_rates.iter()) {
+            let ILegacySettlX::getPaymentReturn {
+                _0: legacy_id,
+                _1: payer,
+                _2: merchant,
+                _3: amount,
+                _4: timestamp,
+                _5: rfce,
+                _6: status,
+            } = self
+                .view_call(legacy, ILegacySettlX::getPaymentCall { payment_id: id })
+                .ok_or(SettlXError::LegacyPaymentNotFound(LegacyPaymentNotFound { id }))?;
+            if legacy_id != id || payer == Address::ZERO || status > PaymentStatus::Paid as u8 {
+                return Err(SettlXError::LegacyPaymentNotFound(LegacyPaymentNotFound { id }));
+            }
+            if self.payments.get(id).payer.get() != Address::ZERO {
+                return Err(SettlXError::PaymentAlreadyImported(PaymentAlreadyImported { id }));
+            }
+            // 1=Accepted and 3=Paid locked a rate; 0=Pending and 2=Rejected did not.
+            let has_rate =
+                status == PaymentStatus::Accepted as u8 || status == PaymentStatus::Paid as u8;
+            if has_rate == rate.is_zero() {
+                return Err(SettlXError::InvalidRate(InvalidRate {}));
+            }
+
+            let mut payment = self.payments.setter(id);
+            payment.id.set(id);
+            payment.payer.set(payer);
+            payment.merchant.set(merchant);
+            payment.amount.set(amount);
+            payment.timestamp.set(timestamp);
+            payment.updated_at.set(now);
+            payment.rfce.set(rfce);
+            payment.status.set(U8::from(status));
+            payment.locked_rate.set(rate);
+            payment.token.set(token);
+            if has_rate {
+                // The legacy contract only ever settled in NGN.
+                payment.currency.set(DEFAULT_CURRENCY);
+            }
+            let escrowed = if status == PaymentStatus::Pending as u8 {
+                payment.expires_at.set(expires_at);
+                payment.legacy_held.set(true);
+                escrow_total += amount;
+                amount
+            } else {
+                U256::ZERO
+            };
+            drop(payment);
+
+            self.merchant_payments.setter(merchant).push(id);
+            self.payer_payments.setter(payer).push(id);
+            self.index_status(id, merchant, status);
+            if id >= self.next_payment_id.get() {
+                self.next_payment_id.set(id + U256::from(1));
+            }
+            self.imported_payments.set(self.imported_payments.get() + U256::from(1));
+
+            log(self.vm(), PaymentImported { id, status, escrowed });
+        }
+
+        if escrow_total > U256::ZERO {
+            let sender = self.vm().msg_sender();
+            let contract_addr = self.vm().contract_address();
+            self.safe_transfer_from(token, sender, contract_addr, escrow_total)?;
+        }
+
+        Ok(())
+    }
+
+    /// Copy the bank-detail hashes of `merchants` from the legacy contract.
+    pub fn import_merchants(&mut self, merchants: Vec<Address>) -> Result<(), SettlXError> {
+        self.only_role(DEFAULT_ADMIN_ROLE)?;
+        if self.migration_state.get().to::<u8>() != MIGRATION_ACTIVE {
+            return Err(SettlXError::InvalidMigrationState(InvalidMigrationState {}));
+        }
+        check_batch_size(merchants.len())?;
+
+        let legacy = self.legacy_contract.get();
+        for merchant in merchants {
+            let ILegacySettlX::getMerchantBankDetailsReturn {
+                _0: bank_name,
+                _1: account_name,
+                _2: account_number,
+            } = self
+                .view_call(legacy, ILegacySettlX::getMerchantBankDetailsCall { merchant })
+                .ok_or(SettlXError::LegacyMerchantNotFound(LegacyMerchantNotFound { merchant }))?;
+            if bank_name == FixedBytes::ZERO {
+                return Err(SettlXError::LegacyMerchantNotFound(LegacyMerchantNotFound { merchant }));
+            }
+
+            let mut info = self.merchants.setter(merchant);
+            if info.is_registered.get() {
+                return Err(SettlXError::MerchantAlreadyImported(MerchantAlreadyImported { merchant }));
+            }
+            info.bank_name.set(bank_name);
+            info.account_name.set(account_name);
+            info.account_number.set(account_number);
+            info.is_registered.set(true);
+            drop(info);
+
+            log(self.vm(), MerchantImported { merchant });
+        }
+
+        Ok(())
+    }
+
+    /// Add `ids` to the open-payment queues matching their current status.
+    /// Only needed for payments written before the queues existed; ids
+    /// already queued are left alone.
+    pub fn reindex_payments(&mut self, ids: Vec<U256>) -> Result<(), SettlXError> {
+        self.only_role(DEFAULT_ADMIN_ROLE)?;
+        check_batch_size(ids.len())?;
+        for id in ids {
+            let payment = self.payments.get(id);
+            let merchant = payment.merchant.get();
+            let status = payment.status.get().to::<u8>();
+            drop(payment);
+            if merchant != Address::ZERO {
+                self.index_status(id, merchant, status);
+            }
+        }
+        Ok(())
+    }
+
+    /// Finish the migration and open the contract. Cannot be undone.
+    pub fn seal_migration(&mut self) -> Result<(), SettlXError> {
+        self.only_role(DEFAULT_ADMIN_ROLE)?;
+        if self.migration_state.get().to::<u8>() != MIGRATION_ACTIVE {
+            return Err(SettlXError::InvalidMigrationState(InvalidMigrationState {}));
+        }
+        self.migration_state.set(U8::from(MIGRATION_SEALED));
+        log(self.vm(), MigrationSealed {
+            legacy: self.legacy_contract.get(),
+            importedPayments: self.imported_payments.get(),
+            nextPaymentId: self.next_payment_id.get(),
+        });
+        Ok(())
+    }
+
+    // ── Configuration ─────────────────────────────────────────────────────────
+
+    /// Charge `fee_bps` of every accepted amount and send it to `recipient`.
+    /// A zero recipient turns the fee off; Pending payments are unaffected
+    /// until they are accepted.
+    pub fn set_protocol_fee(&mut self, fee_bps: U256, recipient: Address) -> Result<(), SettlXError> {
+        self.only_role(DEFAULT_ADMIN_ROLE)?;
+        if fee_bps > U256::from(MAX_FEE_BPS) {
+            return Err(SettlXError::InvalidFee(InvalidFee {}));
+        }
+        self.fee_bps.set(fee_bps);
+        self.fee_recipient.set(recipient);
+        log(self.vm(), ProtocolFeeUpdated { feeBps: fee_bps, recipient });
+        Ok(())
+    }
+
+    /// Set the smallest fee charged on a payment in `token`, in its own units.
+    pub fn set_token_min_fee(&mut self, token: Address, min_fee: U256) -> Result<(), SettlXError> {
+        self.only_role(DEFAULT_ADMIN_ROLE)?;
+        let mut config = self.tokens.setter(token);
+        if !config.listed.get() {
+            return Err(SettlXError::TokenNotAccepted(TokenNotAccepted {}));
+        }
+        config.min_fee.set(min_fee);
+        drop(config);
+        log(self.vm(), TokenMinFeeUpdated { token, minFee: min_fee });
+        Ok(())
+    }
+
+    /// Create or update pricing plan `plan_id` (non-zero). Deactivating a
+    /// plan sends its merchants back to default terms until it is re-enabled.
+    pub fn set_pricing_plan(
+        &mut self,
+        plan_id: U256,
+        fee_bps: U256,
+        fx_spread_bps: U256,
+        min_volume: U256,
+        active: bool,
+    ) -> Result<(), SettlXError> {
+        self.only_role(DEFAULT_ADMIN_ROLE)?;
+        if plan_id == U256::ZERO {
+            return Err(SettlXError::InvalidPlan(InvalidPlan {}));
+        }
+        if fee_bps > U256::from(MAX_FEE_BPS) || fx_spread_bps > U256::from(MAX_FX_SPREAD_BPS) {
+            return Err(SettlXError::InvalidFee(InvalidFee {}));
+        }
+
+        if !self.pricing_plans.get(plan_id).listed.get() {
+            self.pricing_plan_ids.push(plan_id);
+        }
+        let mut plan = self.pricing_plans.setter(plan_id);
+        plan.listed.set(true);
+        plan.active.set(active);
+        plan.fee_bps.set(fee_bps);
+        plan.fx_spread_bps.set(fx_spread_bps);
+        plan.min_volume.set(min_volume);
+        drop(plan);
+
+        log(self.vm(), PricingPlanUpdated {
+            planId: plan_id,
+            feeBps: fee_bps,
+            fxSpreadBps: fx_spread_bps,
+            minVolume: min_volume,
+            active,
+        });
+
+        Ok(())
+    }
+
+    /// Put `merchant` on `plan_id` (0 = default terms). Admin assignments
+    /// skip the volume threshold so negotiated terms can be granted up front.
+    pub fn assign_pricing_plan(&mut self, merchant: Address, plan_id: U256) -> Result<(), SettlXError> {
+        self.only_role(DEFAULT_ADMIN_ROLE)?;
+        if plan_id != U256::ZERO && !self.pricing_plans.get(plan_id).active.get() {
+            return Err(SettlXError::InvalidPlan(InvalidPlan {}));
+        }
+        let mut info = self.merchants.setter(merchant);
+        if !info.is_registered.get() {
+            return Err(SettlXError::NotRegistered(NotRegistered {}));
+        }
+        info.plan_id.set(plan_id);
+        drop(info);
+
+        log(self.vm(), MerchantPlanAssigned { merchant, planId: plan_id });
+
+        Ok(())
+    }
+
+    /// Set the expiry used when a payer passes `expires_in == 0`, and the
+    /// longest expiry a payer may request.
+    pub fn set_payment_expiry_config(
+        &mut self,
+        default_ttl: U256,
+        max_ttl: U256,
+    ) -> Result<(), SettlXError> {
+        self.only_role(DEFAULT_ADMIN_ROLE)?;
+        if default_ttl == U256::ZERO || default_ttl > max_ttl {
+            return Err(SettlXError::InvalidExpiry(InvalidExpiry {}));
+        }
+
+        self.default_payment_ttl.set(default_ttl);
+        self.max_payment_ttl.set(max_ttl);
+
+        log(self.vm(), PaymentExpiryConfigUpdated { defaultTtl: default_ttl, maxTtl: max_ttl });
+
+        Ok(())
+    }
+
+    /// Set how long, in seconds, evidence can be submitted after a dispute opens.
+    pub fn set_dispute_period(&mut self, period: U256) -> Result<(), SettlXError> {
+        self.only_role(DEFAULT_ADMIN_ROLE)?;
+        self.dispute_period.set(period);
+        log(self.vm(), DisputePeriodUpdated { period });
+        Ok(())
+    }
+
+    /// Set how long, in seconds, a Paid payment stays open to disputes after
+    /// it was settled.
+    pub fn set_paid_dispute_window(&mut self, window: U256) -> Result<(), SettlXError> {
+        self.only_role(DEFAULT_ADMIN_ROLE)?;
+        self.paid_dispute_window.set(window);
+        log(self.vm(), PaidDisputeWindowUpdated { window });
+        Ok(())
+    }
+
+    /// Set how long, in seconds, settlers have to settle an escrowed payment.
+    pub fn set_settlement_period(&mut self, period: U256) -> Result<(), SettlXError> {
+        self.only_role(DEFAULT_ADMIN_ROLE)?;
+        self.settlement_period.set(period);
+        log(self.vm(), SettlementPeriodUpdated { period });
+        Ok(())
+    }
+
+    /// List a stablecoin or toggle an already-listed one. `decimals` must
+    /// match the token and cannot change after the first listing.
+    pub fn set_accepted_token(
+        &mut self,
+        token: Address,
+        decimals: u8,
+        enabled: bool,
+    ) -> Result<(), SettlXError> {
+        self.only_role(DEFAULT_ADMIN_ROLE)?;
+        if token == Address::ZERO {
+            return Err(SettlXError::InvalidToken(InvalidToken {}));
+        }
+        self.configure_token(token, decimals, enabled)
+    }
+
+    /// List a fiat settlement currency (ISO 4217, e.g. "KES") or toggle it.
+    pub fn set_currency(
+        &mut self,
+        currency: FixedBytes<3>,
+        enabled: bool,
+    ) -> Result<(), SettlXError> {
+        self.only_role(RATE_ADMIN_ROLE)?;
+        self.configure_currency(currency, enabled)
+    }
+
+    /// Bound locked rates for `currency` to `max_deviation_bps` around `oracle`,
+    /// rejecting answers older than `max_staleness` seconds. Pass a zero
+    /// `oracle` to turn the check off for that currency.
+    pub fn set_currency_oracle(
+        &mut self,
+        currency: FixedBytes<3>,
+        oracle: Address,
+        max_deviation_bps: U256,
+        max_staleness: U256,
+    ) -> Result<(), SettlXError> {
+        self.only_role(RATE_ADMIN_ROLE)?;
+        if !self.currencies.get(currency).listed.get() {
+            return Err(SettlXError::CurrencyNotEnabled(CurrencyNotEnabled {}));
+        }
+        if oracle != Address::ZERO
+            && (max_deviation_bps == U256::ZERO
+                || max_deviation_bps > U256::from(BPS_DENOMINATOR)
+                || max_staleness == U256::ZERO)
+        {
+            return Err(SettlXError::InvalidOracleConfig(InvalidOracleConfig {}));
+        }
+
+        let mut config = self.currencies.setter(currency);
+        config.oracle.set(oracle);
+        config.max_deviation_bps.set(max_deviation_bps);
+        config.max_staleness.set(max_staleness);
+
+        log(self.vm(), CurrencyOracleUpdated {
+            currency,
+            oracle,
+            maxDeviationBps: max_deviation_bps,
+            maxStaleness: max_staleness,
+        });
+
+        Ok(())
+    }
+
+    /// Configure the L2 sequencer uptime feed. While the sequencer is down,
+    /// or until `grace_period` seconds after it comes back, oracle-checked
+    /// acceptances revert.
+    pub fn set_sequencer_uptime_feed(
+        &mut self,
+        feed: Address,
+        grace_period: U256,
+    ) -> Result<(), SettlXError> {
+        self.only_role(RATE_ADMIN_ROLE)?;
+        self.sequencer_uptime_feed.set(feed);
+        self.sequencer_grace_period.set(grace_period);
+        log(self.vm(), SequencerFeedUpdated { feed, gracePeriod: grace_period });
+        Ok(())
+    }
+
+    /// Set the key whose EIP-712 `RateQuote` signatures are honoured.
+    pub fn set_quoter(&mut self, quoter: Address) -> Result<(), SettlXError> {
+        self.only_role(RATE_ADMIN_ROLE)?;
+        self.quoter.set(quoter);
+        log(self.vm(), QuoterUpdated { quoter });
+        Ok(())
+    }
+
+    /// When `required` is true, merchants can no longer type in their own rate
+    /// and must go through `accept_payment_with_quote`.
+    pub fn set_require_rate_quotes(&mut self, required: bool) -> Result<(), SettlXError> {
+        self.only_role(RATE_ADMIN_ROLE)?;
+        self.require_rate_quotes.set(required);
+        log(self.vm(), RateQuotesRequiredUpdated { required });
+        Ok(())
+    }
+
+    // ── Merchant Registration ─────────────────────────────────────────────────
+
+    /// Register bank details for the first time.
+    /// Stores keccak256 hashes on-chain; plaintext emitted in MerchantRegistered event.
+    pub fn register_merchant_bank_details(
+        &mut self,
+        bank_name: String,
+        account_name: String,
+        account_number: String,
+    ) -> Result<(), SettlXError> {
+        self.when_not_migrating()?;
+        if bank_name.is_empty() {
+            return Err(SettlXError::BankNameRequired(BankNameRequired {}));
+        }
+        if account_name.is_empty() {
+            return Err(SettlXError::AccountNameRequired(AccountNameRequired {}));
+        }
+        if account_number.is_empty() {
+            return Err(SettlXError::AccountNumberRequired(AccountNumberRequired {}));
+        }
+
+        let sender = self.vm().msg_sender();
+        let mut merchant = self.merchants.setter(sender);
+
+        merchant.bank_name.set(keccak256(bank_name.as_bytes()));
+        merchant.account_name.set(keccak256(account_name.as_bytes()));
+        merchant.account_number.set(keccak256(account_number.as_bytes()));
+        merchant.is_registered.set(true);
+
+        log(self.vm(), MerchantRegistered {
+            merchant: sender,
+            bankName: bank_name,
+            accountName: account_name,
+            accountNumber: account_number,
+        });
+
+        Ok(())
+    }
+
+    /// Update bank details for an already-registered merchant.
+    ///
+    /// WHY erase() BEFORE set():
+    /// Stylus bytes32 storage slots must be fully zeroed before writing a new value.
+    /// Without erase(), the second+ update silently corrupts the slot because the
+    /// old non-zero bits are OR'd with the new value instead of being replaced.
+    /// erase() zeroes the slot first, making set() work correctly every time.
+    pub fn update_merchant_bank_details(
+        &mut self,
+        bank_name: String,
+        account_name: String,
+        account_number: String,
+    ) -> Result<(), SettlXError> {
+        self.when_not_migrating()?;
+        if bank_name.is_empty() {
+            return Err(SettlXError::BankNameRequired(BankNameRequired {}));
+        }
+        if account_name.is_empty() {
+            return Err(SettlXError::AccountNameRequired(AccountNameRequired {}));
+        }
+        if account_number.is_empty() {
+            return Err(SettlXError::AccountNumberRequired(AccountNumberRequired {}));
+        }
+
+        let sender = self.vm().msg_sender();
+        let mut merchant = self.merchants.setter(sender);
+
+        if !merchant.is_registered.get() {
+            return Err(SettlXError::NotRegistered(NotRegistered {}));
+        }
+
+        // Erase each slot before writing — required for correct bytes32 overwrite
+        // in Stylus. Skipping erase() causes corruption on the 3rd+ update.
+        merchant.bank_name.erase();
+        merchant.bank_name.set(keccak256(bank_name.as_bytes()));
+
+        merchant.account_name.erase();
+        merchant.account_name.set(keccak256(account_name.as_bytes()));
+
+        merchant.account_number.erase();
+        merchant.account_number.set(keccak256(account_number.as_bytes()));
+
+        log(self.vm(), MerchantUpdated {
+            merchant: sender,
+            bankName: bank_name,
+            accountName: account_name,
+            accountNumber: account_number,
+        });
+
+        Ok(())
+    }
+
+    /// Set the currency this merchant is settled in when acceptance does not
+    /// name one. Pass zero to fall back to the contract default (NGN).
+    pub fn set_settlement_currency(&mut self, currency: FixedBytes<3>) -> Result<(), SettlXError> {
+        if currency != FixedBytes::ZERO && !self.currencies.get(currency).enabled.get() {
+            return Err(SettlXError::CurrencyNotEnabled(CurrencyNotEnabled {}));
+        }
+
+        let sender = self.vm().msg_sender();
+        let mut merchant = self.merchants.setter(sender);
+        if !merchant.is_registered.get() {
+            return Err(SettlXError::NotRegistered(NotRegistered {}));
+        }
+        merchant.settlement_currency.set(currency);
+
+        log(self.vm(), MerchantCurrencyUpdated { merchant: sender, currency });
+
+        Ok(())
+    }
+
+    /// Move onto an active pricing plan whose volume threshold the caller's
+    /// accepted volume already meets.
+    pub fn claim_pricing_plan(&mut self, plan_id: U256) -> Result<(), SettlXError> {
+        let plan = self.pricing_plans.get(plan_id);
+        if plan_id == U256::ZERO || !plan.active.get() {
+            return Err(SettlXError::InvalidPlan(InvalidPlan {}));
+        }
+        let min_volume = plan.min_volume.get();
+        drop(plan);
+
+        let sender = self.vm().msg_sender();
+        let mut info = self.merchants.setter(sender);
+        if !info.is_registered.get() {
+            return Err(SettlXError::NotRegistered(NotRegistered {}));
+        }
+        let volume = info.volume.get();
+        if volume < min_volume {
+            return Err(SettlXError::VolumeThresholdNotMet(VolumeThresholdNotMet {
+                volume,
+                minVolume: min_volume,
+            }));
+        }
+        info.plan_id.set(plan_id);
+        drop(info);
+
+        log(self.vm(), MerchantPlanAssigned { merchant: sender, planId: plan_id });
+
+        Ok(())
+    }
+
+    // ── Payment Lifecycle ─────────────────────────────────────────────────────
+
+    /// Escrow `amount` of `token` (in the token's own decimals) for `merchant`.
+    /// The payment expires `expires_in` seconds from now (0 = contract
+    /// default); after that the payer or any keeper can refund it through
+    /// `reclaim_expired_payment`.
+    pub fn pay_merchant(
+        &mut self,
+        token: Address,
+        merchant: Address,
+        amount: U256,
+        rfce: String,
+        expires_in: U256,
+    ) -> Result<(), SettlXError> {
+        self.when_not_paused(PAUSE_PAYMENTS)?;
+        let payer = self.vm().msg_sender();
+        self.create_payment(payer, token, merchant, amount, rfce, expires_in)
+    }
+
+    /// Accept a Pending payment and lock `rate`, expressed as units of
+    /// `currency` per whole token × 1e18. A zero `currency` uses the
+    /// merchant's profile currency, or NGN if the profile has none.
+    /// If the currency has an oracle, `rate` must fall inside its band.
+    pub fn accept_payment_with_rate(
+        &mut self,
+        payment_id: U256,
+        rate: U256,
+        currency: FixedBytes<3>,
+    ) -> Result<(), SettlXError> {
+        self.when_not_paused(PAUSE_ACCEPTANCE)?;
+        if self.require_rate_quotes.get() {
+            return Err(SettlXError::QuoteRequired(QuoteRequired {}));
+        }
+        let sender = self.vm().msg_sender();
+        self.accept_payment(payment_id, sender, None, rate, currency)
+    }
+
+    /// Accept a Pending payment at a rate the platform signed off-chain.
+    ///
+    /// `quote` is `(paymentId, currency, rate, validUntil, nonce, merchant)`
+    /// and `signature` is the quoter's 65-byte EIP-712 signature over it. A
+    /// quote names one payment and one currency, and each nonce can be
+    /// redeemed once per merchant.
+    pub fn accept_payment_with_quote(
+        &mut self,
+        payment_id: U256,
+        quote: (U256, FixedBytes<3>, U256, U256, U256, Address),
+        signature: Bytes,
+    ) -> Result<(), SettlXError> {
+        self.when_not_paused(PAUSE_ACCEPTANCE)?;
+        let sender = self.vm().msg_sender();
+        let (currency, rate) = self.redeem_quote(payment_id, sender, quote, &signature)?;
+        self.accept_payment(payment_id, sender, None, rate, currency)
+    }
+
+    /// Capture `amount` of a Pending authorization at `rate` and refund the
+    /// rest to the payer in the same call. Rate and currency rules are those
+    /// of `accept_payment_with_rate`; fees apply to the captured part only.
+    pub fn capture_payment(
+        &mut self,
+        payment_id: U256,
+        amount: U256,
+        rate: U256,
+        currency: FixedBytes<3>,
+    ) -> Result<(), SettlXError> {
+        self.when_not_paused(PAUSE_ACCEPTANCE)?;
+        if self.require_rate_quotes.get() {
+            return Err(SettlXError::QuoteRequired(QuoteRequired {}));
+        }
+        let sender = self.vm().msg_sender();
+        self.accept_payment(payment_id, sender, Some(amount), rate, currency)
+    }
+
+    /// `capture_payment` at a quoter-signed rate; see `accept_payment_with_quote`.
+    pub fn capture_payment_with_quote(
+        &mut self,
+        payment_id: U256,
+        amount: U256,
+        quote: (U256, FixedBytes<3>, U256, U256, U256, Address),
+        signature: Bytes,
+    ) -> Result<(), SettlXError> {
+        self.when_not_paused(PAUSE_ACCEPTANCE)?;
+        let sender = self.vm().msg_sender();
+        let (currency, rate) = self.redeem_quote(payment_id, sender, quote, &signature)?;
+        self.accept_payment(payment_id, sender, Some(amount), rate, currency)
+    }
+
+    /// Release an uncaptured authorization: the full amount goes back to the payer.
+    pub fn void_payment(&mut self, payment_id: U256) -> Result<(), SettlXError> {
+        self.when_not_paused(PAUSE_ACCEPTANCE)?;
+        let sender = self.vm().msg_sender();
+        let payment = self.payments.get(payment_id);
+
+        if payment.merchant.get() != sender {
+            return Err(SettlXError::NotYourPayment(NotYourPayment {}));
+        }
+        check_transition(payment.status.get(), PaymentStatus::Voided)?;
+
+        let amount = payment.amount.get();
+        let payer = payment.payer.get();
+        let token = payment.token.get();
+        drop(payment);
+        self.set_status(payment_id, PaymentStatus::Voided)?;
+        self.payments.setter(payment_id).refunded_amount.set(amount);
+
+        self.safe_transfer(token, payer, amount)?;
+
+        log(self.vm(), PaymentVoided { id: payment_id, amount });
+
+        Ok(())
+    }
+
+    pub fn reject_payment(&mut self, payment_id: U256) -> Result<(), SettlXError> {
+        self.when_not_paused(PAUSE_ACCEPTANCE)?;
+        let sender = self.vm().msg_sender();
+        self.reject(payment_id, sender)
+    }
+
+    /// Let the payer withdraw a Pending payment before the merchant acts on it.
+    pub fn cancel_payment(&mut self, payment_id: U256) -> Result<(), SettlXError> {
+        self.when_not_paused(PAUSE_PAYMENTS)?;
+        let sender = self.vm().msg_sender();
+        let payment = self.payments.get(payment_id);
+
+        if payment.payer.get() != sender {
+            return Err(SettlXError::NotYourPayment(NotYourPayment {}));
+        }
+        check_transition(payment.status.get(), PaymentStatus::Cancelled)?;
+
+        let amount = payment.amount.get();
+        let token = payment.token.get();
+        drop(payment);
+        self.set_status(payment_id, PaymentStatus::Cancelled)?;
+        self.payments.setter(payment_id).refunded_amount.set(amount);
+
+        self.safe_transfer(token, sender, amount)?;
+
+        log(self.vm(), PaymentCancelled { id: payment_id });
+
+        Ok(())
+    }
+
+    /// Refund a Pending payment whose expiry has passed. Callable by anyone so
+    /// keepers can clean up on behalf of payers; funds always go to the payer.
+    pub fn reclaim_expired_payment(&mut self, payment_id: U256) -> Result<(), SettlXError> {
+        self.when_not_paused(PAUSE_PAYMENTS)?;
+        let sender = self.vm().msg_sender();
+        let now = U256::from(self.vm().block_timestamp());
+        let payment = self.payments.get(payment_id);
+
+        if payment.payer.get() == Address::ZERO {
+            return Err(SettlXError::PaymentNotFound(PaymentNotFound { id: payment_id }));
+        }
+        check_transition(payment.status.get(), PaymentStatus::Expired)?;
+        if now < payment.expires_at.get() {
+            return Err(SettlXError::PaymentNotExpired(PaymentNotExpired {}));
+        }
+
+        let payer = payment.payer.get();
+        let amount = payment.amount.get();
+        let token = payment.token.get();
+        drop(payment);
+        self.set_status(payment_id, PaymentStatus::Expired)?;
+        self.payments.setter(payment_id).refunded_amount.set(amount);
+
+        self.safe_transfer(token, payer, amount)?;
+
+        log(self.vm(), PaymentExpired { id: payment_id, reclaimedBy: sender });
+
+        Ok(())
+    }
+
+    pub fn mark_as_paid(&mut self, payment_id: U256) -> Result<(), SettlXError> {
+        self.when_not_paused(PAUSE_SETTLEMENT)?;
+        self.only_role(SETTLER_ROLE)?;
+        self.mark_paid(payment_id, FixedBytes::ZERO)
+    }
+
+    /// `mark_as_paid`, also recording a hash of the off-chain payout reference
+    /// (bank transfer id, payout batch) for later reconciliation.
+    pub fn mark_as_paid_with_reference(
+        &mut self,
+        payment_id: U256,
+        reference: FixedBytes<32>,
+    ) -> Result<(), SettlXError> {
+        self.when_not_paused(PAUSE_SETTLEMENT)?;
+        self.only_role(SETTLER_ROLE)?;
+        self.mark_paid(payment_id, reference)
+    }
+
+    /// Release an escrowed payment to the merchant once the settlement deadline
+    /// has passed without `mark_as_paid`. Either the merchant or the payer can
+    /// trigger it: the payer's obligation is met and the merchant gets tokens
+    /// instead of the fiat payout that never arrived.
+    pub fn release_overdue_escrow(&mut self, payment_id: U256) -> Result<(), SettlXError> {
+        self.when_not_paused(PAUSE_SETTLEMENT)?;
+        let sender = self.vm().msg_sender();
+        let now = U256::from(self.vm().block_timestamp());
+        let payment = self.payments.get(payment_id);
+
+        let merchant = payment.merchant.get();
+        if payment.payer.get() != sender && merchant != sender {
+            return Err(SettlXError::NotYourPayment(NotYourPayment {}));
+        }
+        check_transition(payment.status.get(), PaymentStatus::Released)?;
+        let escrowed = payment.escrowed.get();
+        if escrowed == U256::ZERO {
+            return Err(SettlXError::NotEscrowed(NotEscrowed {}));
+        }
+        if now <= payment.settlement_deadline.get() {
+            return Err(SettlXError::SettlementNotOverdue(SettlementNotOverdue {}));
+        }
+
+        let token = payment.token.get();
+        drop(payment);
+        self.set_status(payment_id, PaymentStatus::Released)?;
+        self.payments.setter(payment_id).escrowed.set(U256::ZERO);
+
+        self.safe_transfer(token, merchant, escrowed)?;
+
+        log(self.vm(), EscrowReleased { id: payment_id, merchant, amount: escrowed });
+
+        Ok(())
+    }
+
+    /// Refund `amount` of a Paid payment to its payer, e.g. for returned
+    /// goods. The merchant, or an admin acting for them, funds it from their
+    /// own balance (needs an allowance in the payment token). Partial refunds
+    /// can repeat until the payer has had back everything they paid.
+    pub fn refund_payment(&mut self, payment_id: U256, amount: U256) -> Result<(), SettlXError> {
+        self.when_not_paused(PAUSE_SETTLEMENT)?;
+        let sender = self.vm().msg_sender();
+        let payment = self.payments.get(payment_id);
+
+        if payment.merchant.get() != sender && !self.roles.get(DEFAULT_ADMIN_ROLE).get(sender) {
+            return Err(SettlXError::NotYourPayment(NotYourPayment {}));
+        }
+        if payment.status.get().to::<u8>() == PaymentStatus::Disputed as u8 {
+            return Err(SettlXError::PaymentDisputed(PaymentDisputed { id: payment_id }));
+        }
+        check_transition(payment.status.get(), PaymentStatus::Refunded)?;
+        // U256 arithmetic wraps in every build profile, so a huge `amount` must not wrap past the bound.
+        let total_refunded = match payment.refunded_amount.get().checked_add(amount) {
+            Some(total) if amount != U256::ZERO && total <= payment.amount.get() => total,
+            _ => return Err(SettlXError::InvalidAmount(InvalidAmount {})),
+        };
+        let payer = payment.payer.get();
+        let merchant = payment.merchant.get();
+        let token = payment.token.get();
+        drop(payment);
+
+        self.set_status(payment_id, PaymentStatus::Refunded)?;
+        self.payments.setter(payment_id).refunded_amount.set(total_refunded);
+        self.reduce_volume(merchant, token, amount);
+
+        self.safe_transfer_from(token, sender, payer, amount)?;
+
+        log(self.vm(), PaymentRefunded {
+            id: payment_id,
+            refundedBy: sender,
+            amount,
+            totalRefunded: total_refunded,
+        });
+
+        Ok(())
+    }
+
+    // ── Batch Operations ──────────────────────────────────────────────────────
+    // Each batch runs the single-item checks per entry. With `atomic` set, the
+    // first failing entry reverts the whole batch with its error. Otherwise
+    // failing entries are skipped. The returned vector has one entry per
+    // item: empty on success, or the ABI-encoded error. A failed token
+    // transfer reverts the whole batch, except in `batch_pay`, where the
+    // transfer comes before any write and the item is skipped like any other.
+
+    /// `mark_as_paid` for each id. `references` is empty, or holds one
+    /// settlement reference per id as in `mark_as_paid_with_reference`.
+    pub fn batch_mark_as_paid(
+        &mut self,
+        payment_ids: Vec<U256>,
+        references: Vec<FixedBytes<32>>,
+        atomic: bool,
+    ) -> Result<Vec<Bytes>, SettlXError> {
+        self.when_not_paused(PAUSE_SETTLEMENT)?;
+        self.only_role(SETTLER_ROLE)?;
+        check_batch_size(payment_ids.len())?;
+        if !references.is_empty() && references.len() != payment_ids.len() {
+            return Err(SettlXError::LengthMismatch(LengthMismatch {}));
+        }
+
+        let mut results = Vec::with_capacity(payment_ids.len());
+        for (i, payment_id) in payment_ids.into_iter().enumerate() {
+            let reference = references.get(i).copied().unwrap_or_default();
+            let result = self.mark_paid(payment_id, reference);
+            record_batch_result(&mut results, result, atomic, true)?;
+        }
+        Ok(results)
+    }
+
+    /// `accept_payment_with_rate` for each id, in the merchant's default currency.
+    pub fn batch_accept(
+        &mut self,
+        payment_ids: Vec<U256>,
+        rates: Vec<U256>,
+        atomic: bool,
+    ) -> Result<Vec<Bytes>, SettlXError> {
+        self.when_not_paused(PAUSE_ACCEPTANCE)?;
+        if self.require_rate_quotes.get() {
+            return Err(SettlXError::QuoteRequired(QuoteRequired {}));
+        }
+        if payment_ids.len() != rates.len() {
+            return Err(SettlXError::LengthMismatch(LengthMismatch {}));
+        }
+        check_batch_size(payment_ids.len())?;
+
+        let sender = self.vm().msg_sender();
+        let mut results = Vec::with_capacity(payment_ids.len());
+        for (payment_id, rate) in payment_ids.into_iter().zip(rates) {
+            let result = self.accept_payment(payment_id, sender, None, rate, FixedBytes::ZERO);
+            record_batch_result(&mut results, result, atomic, true)?;
+        }
+        Ok(results)
+    }
+
+    /// `reject_payment` for each id.
+    pub fn batch_reject(
+        &mut self,
+        payment_ids: Vec<U256>,
+        atomic: bool,
+    ) -> Result<Vec<Bytes>, SettlXError> {
+        self.when_not_paused(PAUSE_ACCEPTANCE)?;
+        check_batch_size(payment_ids.len())?;
+
+        let sender = self.vm().msg_sender();
+        let mut results = Vec::with_capacity(payment_ids.len());
+        for payment_id in payment_ids {
+            let result = self.reject(payment_id, sender);
+            record_batch_result(&mut results, result, atomic, true)?;
+        }
+        Ok(results)
+    }
+
+    /// `pay_merchant` in `token` for each (merchant, amount, reference), with
+    /// the default expiry.
+    pub fn batch_pay(
+        &mut self,
+        token: Address,
+        merchants: Vec<Address>,
+        amounts: Vec<U256>,
+        refs: Vec<String>,
+        atomic: bool,
+    ) -> Result<Vec<Bytes>, SettlXError> {
+        self.when_not_paused(PAUSE_PAYMENTS)?;
+        if merchants.len() != amounts.len() || merchants.len() != refs.len() {
+            return Err(SettlXError::LengthMismatch(LengthMismatch {}));
+        }
+        check_batch_size(merchants.len())?;
+
+        let payer = self.vm().msg_sender();
+        let mut results = Vec::with_capacity(merchants.len());
+        for ((merchant, amount), rfce) in merchants.into_iter().zip(amounts).zip(refs) {
+            let result = self.create_payment(payer, token, merchant, amount, rfce, U256::ZERO);
+            record_batch_result(&mut results, result, atomic, false)?;
+        }
+        Ok(results)
+    }
+
+    // ── Disputes ──────────────────────────────────────────────────────────────
+
+    /// Open a dispute on an Accepted or Paid payment. Either the payer or the
+    /// merchant may open it; the payment is frozen as Disputed until the
+    /// arbitrator rules or the opener withdraws. A Paid payment can only be
+    /// disputed within `paid_dispute_window` of being settled.
+    pub fn open_dispute(
+        &mut self,
+        payment_id: U256,
+        evidence_hash: FixedBytes<32>,
+    ) -> Result<(), SettlXError> {
+        self.when_not_migrating()?;
+        let sender = self.vm().msg_sender();
+        let now = U256::from(self.vm().block_timestamp());
+        let deadline = now.saturating_add(self.dispute_period.get());
+
+        let payment = self.payments.get(payment_id);
+        let payer = payment.payer.get();
+        if payer != sender && payment.merchant.get() != sender {
+            return Err(SettlXError::NotYourPayment(NotYourPayment {}));
+        }
+        let status = payment.status.get();
+        if status.to::<u8>() == PaymentStatus::Paid as u8 {
+            // Records settled before paid_at existed fall back to their last status change.
+            let paid_at = match payment.paid_at.get() {
+                U256::ZERO => payment.updated_at.get(),
+                paid_at => paid_at,
+            };
+            let closed_at = paid_at.saturating_add(self.paid_dispute_window.get());
+            if now > closed_at {
+                return Err(SettlXError::DisputeWindowClosed(DisputeWindowClosed { closedAt: closed_at }));
+            }
+        }
+        drop(payment);
+        // A withdrawn dispute may be reopened; a resolved one is final.
+        let state = self.disputes.get(payment_id).state.get().to::<u8>();
+        if state != DISPUTE_NONE && state != DISPUTE_WITHDRAWN {
+            return Err(SettlXError::DisputeAlreadyOpened(DisputeAlreadyOpened {}));
+        }
+        self.set_status(payment_id, PaymentStatus::Disputed)?;
+
+        let mut dispute = self.disputes.setter(payment_id);
+        dispute.opened_by.set(sender);
+        dispute.evidence_hash.erase();
+        dispute.evidence_hash.set(evidence_hash);
+        dispute.opened_at.set(now);
+        dispute.deadline.set(deadline);
+        dispute.state.set(U8::from(DISPUTE_OPEN));
+        dispute.previous_status.set(status);
+        dispute.payer_submitted.set(sender == payer);
+        dispute.merchant_submitted.set(sender != payer);
+
+        log(self.vm(), DisputeOpened {
+            id: payment_id,
+            openedBy: sender,
+            evidenceHash: evidence_hash,
+            deadline,
+        });
+
+        Ok(())
+    }
+
+    /// Add evidence to an open dispute. Either party may call this until the deadline.
+    pub fn submit_dispute_evidence(
+        &mut self,
+        payment_id: U256,
+        evidence_hash: FixedBytes<32>,
+    ) -> Result<(), SettlXError> {
+        let sender = self.vm().msg_sender();
+        let now = U256::from(self.vm().block_timestamp());
+
+        let payment = self.payments.get(payment_id);
+        let payer = payment.payer.get();
+        if payer != sender && payment.merchant.get() != sender {
+            return Err(SettlXError::NotYourPayment(NotYourPayment {}));
+        }
+        drop(payment);
+
+        let mut dispute = self.disputes.setter(payment_id);
+        if dispute.state.get().to::<u8>() != DISPUTE_OPEN {
+            return Err(SettlXError::NoOpenDispute(NoOpenDispute {}));
+        }
+        if now > dispute.deadline.get() {
+            return Err(SettlXError::DisputeDeadlinePassed(DisputeDeadlinePassed {}));
+        }
+
+        // Same erase-before-set rule as merchant bank details.
+        dispute.evidence_hash.erase();
+        dispute.evidence_hash.set(evidence_hash);
+        if sender == payer {
+            dispute.payer_submitted.set(true);
+        } else {
+            dispute.merchant_submitted.set(true);
+        }
+
+        log(self.vm(), DisputeEvidenceSubmitted {
+            id: payment_id,
+            submittedBy: sender,
+            evidenceHash: evidence_hash,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw a dispute before it is ruled on. Only the party that opened it
+    /// may withdraw, and the payment returns to the status it had before.
+    pub fn withdraw_dispute(&mut self, payment_id: U256) -> Result<(), SettlXError> {
+        let sender = self.vm().msg_sender();
+
+        let mut dispute = self.disputes.setter(payment_id);
+        if dispute.state.get().to::<u8>() != DISPUTE_OPEN {
+            return Err(SettlXError::NoOpenDispute(NoOpenDispute {}));
+        }
+        if dispute.opened_by.get() != sender {
+            return Err(SettlXError::NotYourPayment(NotYourPayment {}));
+        }
+        dispute.state.set(U8::from(DISPUTE_WITHDRAWN));
+        let previous_status = dispute.previous_status.get().to::<u8>();
+        drop(dispute);
+
+        let previous_status = PaymentStatus::from_u8(previous_status).ok_or(
+            SettlXError::InvalidTransition(InvalidTransition {
+                from: PaymentStatus::Disputed as u8,
+                to: previous_status,
+            }),
+        )?;
+        self.leave_dispute(payment_id, previous_status)?;
+
+        log(self.vm(), DisputeWithdrawn { id: payment_id });
+
+        Ok(())
+    }
+
+    /// Arbitrator ruling on an open dispute:
+    ///   1 = RefundPayer         — payer gets back everything captured, fee included (status → Refunded)
+    ///   2 = ForceSettle         — payment is treated as settled (status → Paid)
+    ///   3 = CompensateMerchant  — merchant gets `compensation`, at most the net amount (status → Paid)
+    ///
+    /// The arbitrator waits for the evidence deadline unless both parties have
+    /// already had their say. Payouts come out of the payment's escrow first;
+    /// the rest (including a refunded fee) is pulled from the treasury and
+    /// needs an allowance in the payment token. Escrow left over after the
+    /// ruling goes to the treasury, as it would have on `mark_as_paid`.
+    pub fn rule_on_dispute(
+        &mut self,
+        payment_id: U256,
+        ruling: u8,
+        compensation: U256,
+    ) -> Result<(), SettlXError> {
+        self.when_not_paused(PAUSE_SETTLEMENT)?;
+        self.only_role(ARBITRATOR_ROLE)?;
+        let now = U256::from(self.vm().block_timestamp());
+        let dispute = self.disputes.get(payment_id);
+        if dispute.state.get().to::<u8>() != DISPUTE_OPEN {
+            return Err(SettlXError::NoOpenDispute(NoOpenDispute {}));
+        }
+        let deadline = dispute.deadline.get();
+        let both_heard = dispute.payer_submitted.get() && dispute.merchant_submitted.get();
+        drop(dispute);
+        if now < deadline && !both_heard {
+            return Err(SettlXError::DisputeDeadlineNotReached(DisputeDeadlineNotReached { deadline }));
+        }
+
+        let payment = self.payments.get(payment_id);
+        let payer = payment.payer.get();
+        let merchant = payment.merchant.get();
+        let captured = captured_amount(&payment);
+        // The merchant never received the protocol fee, so compensation stops at the net amount.
+        let net = captured - payment.fee.get();
+        let escrowed = payment.escrowed.get();
+        let token = payment.token.get();
+        drop(payment);
+
+        let (new_status, recipient, payout) = match ruling {
+            RULING_REFUND_PAYER => (PaymentStatus::Refunded, payer, captured),
+            RULING_FORCE_SETTLE => (PaymentStatus::Paid, Address::ZERO, U256::ZERO),
+            RULING_COMPENSATE_MERCHANT => {
+                if compensation == U256::ZERO || compensation > net {
+                    return Err(SettlXError::InvalidAmount(InvalidAmount {}));
+                }
+                (PaymentStatus::Paid, merchant, compensation)
+            }
+            _ => return Err(SettlXError::InvalidRuling(InvalidRuling {})),
+        };
+        let compensation = if ruling == RULING_COMPENSATE_MERCHANT { payout } else { U256::ZERO };
+
+        let mut dispute = self.disputes.setter(payment_id);
+        dispute.state.set(U8::from(DISPUTE_RESOLVED));
+        dispute.ruling.set(U8::from(ruling));
+        dispute.compensation.set(compensation);
+        dispute.resolved_at.set(now);
+        drop(dispute);
+
+        let mut payment = self.payments.setter(payment_id);
+        payment.escrowed.set(U256::ZERO);
+        if recipient == payer {
+            let refunded = payment.refunded_amount.get();
+            payment.refunded_amount.set(refunded + payout);
+        }
+        drop(payment);
+        self.leave_dispute(payment_id, new_status)?;
+        if recipient == payer {
+            self.reduce_volume(merchant, token, payout);
+        }
+
+        let treasury = self.treasury.get();
+        let from_escrow = payout.min(escrowed);
+        if from_escrow > U256::ZERO {
+            self.safe_transfer(token, recipient, from_escrow)?;
+        }
+        if payout > from_escrow {
+            self.safe_transfer_from(token, treasury, recipient, payout - from_escrow)?;
+        }
+        if escrowed > from_escrow {
+            self.safe_transfer(token, treasury, escrowed - from_escrow)?;
+        }
+
+        log(self.vm(), DisputeResolved { id: payment_id, ruling, compensation });
+
+        Ok(())
+    }
+
+    // ── Read-only Getters ─────────────────────────────────────────────────────
+
+    pub fn has_role(&self, role: FixedBytes<32>, account: Address) -> bool {
+        self.roles.get(role).get(account)
+    }
+
+    pub fn default_admin_role(&self) -> FixedBytes<32> {
+        DEFAULT_ADMIN_ROLE
+    }
+
+    pub fn settler_role(&self) -> FixedBytes<32> {
+        SETTLER_ROLE
+    }
+
+    pub fn treasury_role(&self) -> FixedBytes<32> {
+        TREASURY_ROLE
+    }
+
+    pub fn pauser_role(&self) -> FixedBytes<32> {
+        PAUSER_ROLE
+    }
+
+    pub fn rate_admin_role(&self) -> FixedBytes<32> {
+        RATE_ADMIN_ROLE
+    }
+
+    pub fn arbitrator_role(&self) -> FixedBytes<32> {
+        ARBITRATOR_ROLE
+    }
+
+    pub fn upgrader_role(&self) -> FixedBytes<32> {
+        UPGRADER_ROLE
+    }
+
+    pub fn get_admin(&self) -> Address {
+        self.admin.get()
+    }
+
+    pub fn get_pending_admin(&self) -> Address {
+        self.pending_admin.get()
+    }
+
+    /// Returns (initialized_version, version_of_this_build); 0 = uninitialised.
+    /// While they differ, the admin should call `reinitialize`.
+    pub fn get_initialized_version(&self) -> (u64, u64) {
+        (self.initialized_version.get().to::<u64>(), VERSION)
+    }
+
+    /// Returns (implementation, pending_implementation, upgrade_ready_at, upgrade_delay).
+    /// `implementation` is zero when the contract is not running behind a proxy.
+    pub fn get_upgrade_state(&self) -> (Address, Address, U256, U256) {
+        (
+            self.implementation(),
+            self.pending_implementation.get(),
+            self.upgrade_ready_at.get(),
+            self.upgrade_delay.get(),
+        )
+    }
+
+    /// Returns (migration_state, legacy_contract, legacy_token, imported_payments).
+    /// migration_state: 0=None 1=Active 2=Sealed.
+    pub fn get_migration_state(&self) -> (u8, Address, Address, U256) {
+        (
+            self.migration_state.get().to::<u8>(),
+            self.legacy_contract.get(),
+            self.legacy_token.get(),
+            self.imported_payments.get(),
+        )
+    }
+
+    pub fn get_treasury(&self) -> Address {
+        self.treasury.get()
+    }
+
+    /// Returns (paused_flags, paused_since, grace_period).
+    /// Flags: 1=Payments 2=Acceptance 4=Settlement.
+    pub fn get_pause_state(&self) -> (u8, U256, U256) {
+        (
+            self.paused.get().to::<u8>(),
+            self.paused_since.get(),
+            self.pause_grace_period.get(),
+        )
+    }
+
+    /// Returns every payment id for `merchant`, oldest first. Busy merchants
+    /// should page with `get_merchant_payment_ids_page` instead.
+    pub fn get_merchant_payment_ids(&self, merchant: Address) -> Vec<U256> {
+        let payments = self.merchant_payments.get(merchant);
+        let mut result = Vec::new();
+        for i in 0..payments.len() {
+            result.push(payments.get(i).unwrap());
+        }
+        result
+    }
+
+    /// Returns every payment id for `payer`, oldest first. See
+    /// `get_payer_payment_ids_page` for long histories.
+    pub fn get_payer_payment_ids(&self, payer: Address) -> Vec<U256> {
+        let payments = self.payer_payments.get(payer);
+        let mut result = Vec::new();
+        for i in 0..payments.len() {
+            result.push(payments.get(i).unwrap());
+        }
+        result
+    }
+
+    /// Returns (ids, total) for all Pending payments: up to `limit` (max 100)
+    /// ids after `offset`, and the queue length. Queue order is arbitrary.
+    pub fn get_pending_queue(&self, offset: U256, limit: U256) -> (Vec<U256>, U256) {
+        self.pending_payments.page(offset, limit)
+    }
+
+    /// Returns (ids, total) for Accepted payments awaiting settlement, paged
+    /// like `get_pending_queue`. This is the settlement desk's work list.
+    pub fn get_settlement_queue(&self, offset: U256, limit: U256) -> (Vec<U256>, U256) {
+        self.awaiting_settlement.page(offset, limit)
+    }
+
+    /// `get_pending_queue` for one merchant: their inbox.
+    pub fn get_merchant_pending_queue(
+        &self,
+        merchant: Address,
+        offset: U256,
+        limit: U256,
+    ) -> (Vec<U256>, U256) {
+        self.merchant_pending.get(merchant).page(offset, limit)
+    }
+
+    /// `get_settlement_queue` for one merchant.
+    pub fn get_merchant_settlement_queue(
+        &self,
+        merchant: Address,
+        offset: U256,
+        limit: U256,
+    ) -> (Vec<U256>, U256) {
+        self.merchant_awaiting_settlement.get(merchant).page(offset, limit)
+    }
+
+    pub fn get_merchant_payment_count(&self, merchant: Address) -> U256 {
+        U256::from(self.merchant_payments.get(merchant).len())
+    }
+
+    pub fn get_payer_payment_count(&self, payer: Address) -> U256 {
+        U256::from(self.payer_payments.get(payer).len())
+    }
+
+    /// Returns up to `limit` (max 100) of `merchant`'s payment ids, newest
+    /// first, after skipping the `offset` most recent.
+    pub fn get_merchant_payment_ids_page(
+        &self,
+        merchant: Address,
+        offset: U256,
+        limit: U256,
+    ) -> Vec<U256> {
+        page_newest_first(&self.merchant_payments.get(merchant), offset, limit)
+    }
+
+    /// Returns up to `limit` (max 100) of `payer`'s payment ids, newest
+    /// first, after skipping the `offset` most recent.
+    pub fn get_payer_payment_ids_page(&self, payer: Address, offset: U256, limit: U256) -> Vec<U256> {
+        page_newest_first(&self.payer_payments.get(payer), offset, limit)
+    }
+
+    /// Returns (ids, next_cursor): up to `limit` (max 100) of `merchant`'s
+    /// payment ids in `status`, newest first. Start with cursor 0 and pass
+    /// back `next_cursor` for the next page; each call inspects at most 500
+    /// entries, so a short page does not mean the end. The history is
+    /// exhausted once `next_cursor` equals the payment count.
+    pub fn get_merchant_payment_ids_by_status(
+        &self,
+        merchant: Address,
+        status: u8,
+        cursor: U256,
+        limit: U256,
+    ) -> (Vec<U256>, U256) {
+        self.filter_newest_first(&self.merchant_payments.get(merchant), status, cursor, limit)
+    }
+
+    /// `get_merchant_payment_ids_by_status` over `payer`'s history.
+    pub fn get_payer_payment_ids_by_status(
+        &self,
+        payer: Address,
+        status: u8,
+        cursor: U256,
+        limit: U256,
+    ) -> (Vec<U256>, U256) {
+        self.filter_newest_first(&self.payer_payments.get(payer), status, cursor, limit)
+    }
+
+    /// Returns (id, payer, merchant, amount, timestamp, rfce_hash, status, refunded_amount).
+    /// rfce is a keccak256 hash — get plaintext from PaymentCreated events.
+    /// For the locked rate and every other field, use `get_payment_details`.
+    /// refunded_amount is everything returned to the payer so far.
+    pub fn get_payment(
+        &self,
+        payment_id: U256,
+    ) -> (U256, Address, Address, U256, U256, FixedBytes<32>, u8, U256) {
+        let payment = self.payments.get(payment_id);
+        (
+            payment.id.get(),
+            payment.payer.get(),
+            payment.merchant.get(),
+            payment.amount.get(),
+            payment.timestamp.get(),
+            payment.rfce.get(),
+            payment.status.get().to::<u8>(),
+            payment.refunded_amount.get(),
+        )
+    }
+
+    /// Returns every field of a payment, including the locked rate and the
+    /// computed fiat amount. Unknown ids return an all-zero struct.
+    pub fn get_payment_details(&self, payment_id: U256) -> Result<PaymentDetails, SettlXError> {
+        let payment = self.payments.get(payment_id);
+        let locked_rate = payment.locked_rate.get();
+        let captured = if locked_rate == U256::ZERO {
+            U256::ZERO
+        } else {
+            captured_amount(&payment)
+        };
+        Ok(PaymentDetails {
+            id: payment.id.get(),
+            payer: payment.payer.get(),
+            merchant: payment.merchant.get(),
+            token: payment.token.get(),
+            amount: payment.amount.get(),
+            capturedAmount: captured,
+            refundedAmount: payment.refunded_amount.get(),
+            fee: payment.fee.get(),
+            escrowed: payment.escrowed.get(),
+            rfce: payment.rfce.get(),
+            status: payment.status.get().to::<u8>(),
+            currency: payment.currency.get(),
+            lockedRate: locked_rate,
+            fiatAmount: self.get_fiat_amount(payment_id)?,
+            planId: payment.plan_id.get(),
+            oracleRoundId: payment.oracle_round_id.get(),
+            createdAt: payment.timestamp.get(),
+            expiresAt: payment.expires_at.get(),
+            settlementDeadline: payment.settlement_deadline.get(),
+            updatedAt: payment.updated_at.get(),
+            acceptedAt: payment.accepted_at.get(),
+            rejectedAt: payment.rejected_at.get(),
+            paidAt: payment.paid_at.get(),
+            cancelledAt: payment.cancelled_at.get(),
+            expiredAt: payment.expired_at.get(),
+            refundedAt: payment.refunded_at.get(),
+            disputedAt: payment.disputed_at.get(),
+            releasedAt: payment.released_at.get(),
+            voidedAt: payment.voided_at.get(),
+            settlementRef: payment.settlement_ref.get(),
+        })
+    }
+
+    /// `get_payment_details` for each id, in order — one call per dashboard
+    /// page of at most MAX_PAGE_SIZE ids.
+    pub fn get_payments(&self, payment_ids: Vec<U256>) -> Result<Vec<PaymentDetails>, SettlXError> {
+        if payment_ids.len() > MAX_PAGE_SIZE {
+            return Err(SettlXError::BatchTooLarge(BatchTooLarge {
+                size: U256::from(payment_ids.len()),
+                maxSize: U256::from(MAX_PAGE_SIZE),
+            }));
+        }
+        payment_ids
+            .into_iter()
+            .map(|id| self.get_payment_details(id))
+            .collect()
+    }
+
+    /// Returns the unix timestamp after which a Pending payment can be reclaimed.
+    pub fn get_payment_expiry(&self, payment_id: U256) -> U256 {
+        self.payments.get(payment_id).expires_at.get()
+    }
+
+    /// Returns (default_ttl, max_ttl) in seconds.
+    pub fn get_payment_expiry_config(&self) -> (U256, U256) {
+        (self.default_payment_ttl.get(), self.max_payment_ttl.get())
+    }
+
+    /// Returns the stablecoin a payment was made in.
+    pub fn get_payment_token(&self, payment_id: U256) -> Address {
+        self.payments.get(payment_id).token.get()
+    }
+
+    /// Returns the ISO 4217 settlement currency locked on acceptance (zero before).
+    pub fn get_payment_currency(&self, payment_id: U256) -> FixedBytes<3> {
+        self.payments.get(payment_id).currency.get()
+    }
+
+    /// Returns the oracle round the locked rate was checked against (0 = unchecked).
+    pub fn get_payment_oracle_round(&self, payment_id: U256) -> U256 {
+        self.payments.get(payment_id).oracle_round_id.get()
+    }
+
+    /// Returns the fiat value of an accepted payment in its settlement currency, scaled by 1e18:
+    /// (captured - fee) normalised to 18 decimals × locked_rate / 1e18. Zero until accepted.
+    pub fn get_fiat_amount(&self, payment_id: U256) -> Result<U256, SettlXError> {
+        let payment = self.payments.get(payment_id);
+        let decimals = self.tokens.get(payment.token.get()).decimals.get().to::<u8>();
+        let normalised = normalise_amount(captured_amount(&payment) - payment.fee.get(), decimals);
+        fiat_amount(normalised, payment.locked_rate.get())
+    }
+
+    /// Returns who moved the payment and when: (created_at, accepted_at,
+    /// accepted_by, rejected_at, rejected_by, paid_at, paid_by,
+    /// settlement_ref, updated_at, updated_by). Unreached steps are zero.
+    pub fn get_payment_audit_trail(
+        &self,
+        payment_id: U256,
+    ) -> (U256, U256, Address, U256, Address, U256, Address, FixedBytes<32>, U256, Address) {
+        let payment = self.payments.get(payment_id);
+        (
+            payment.timestamp.get(),
+            payment.accepted_at.get(),
+            payment.accepted_by.get(),
+            payment.rejected_at.get(),
+            payment.rejected_by.get(),
+            payment.paid_at.get(),
+            payment.paid_by.get(),
+            payment.settlement_ref.get(),
+            payment.updated_at.get(),
+            payment.updated_by.get(),
+        )
+    }
+
+    /// Returns (authorized_amount, captured_amount, refunded_amount).
+    /// captured_amount is zero until the payment is accepted or captured.
+    pub fn get_payment_amounts(&self, payment_id: U256) -> (U256, U256, U256) {
+        let payment = self.payments.get(payment_id);
+        let captured = if payment.locked_rate.get() == U256::ZERO {
+            U256::ZERO
+        } else {
+            captured_amount(&payment)
+        };
+        (payment.amount.get(), captured, payment.refunded_amount.get())
+    }
+
+    /// Returns the protocol fee taken from `payment_id` at acceptance.
+    pub fn get_payment_fee(&self, payment_id: U256) -> U256 {
+        self.payments.get(payment_id).fee.get()
+    }
+
+    /// Returns the pricing plan applied to `payment_id` at acceptance (0 = default terms).
+    pub fn get_payment_plan(&self, payment_id: U256) -> U256 {
+        self.payments.get(payment_id).plan_id.get()
+    }
+
+    /// Returns every pricing plan id ever created, active or not.
+    pub fn get_pricing_plans(&self) -> Vec<U256> {
+        let mut result = Vec::new();
+        for i in 0..self.pricing_plan_ids.len() {
+            result.push(self.pricing_plan_ids.get(i).unwrap());
+        }
+        result
+    }
+
+    /// Returns (active, fee_bps, fx_spread_bps, min_volume).
+    pub fn get_pricing_plan(&self, plan_id: U256) -> (bool, U256, U256, U256) {
+        let plan = self.pricing_plans.get(plan_id);
+        (
+            plan.active.get(),
+            plan.fee_bps.get(),
+            plan.fx_spread_bps.get(),
+            plan.min_volume.get(),
+        )
+    }
+
+    /// Returns (plan_id, accepted_volume). Volume is normalised to 18 decimals.
+    pub fn get_merchant_plan(&self, merchant: Address) -> (U256, U256) {
+        let info = self.merchants.get(merchant);
+        (info.plan_id.get(), info.volume.get())
+    }
+
+    /// Returns (fee_bps, fee_recipient). No fee is charged while the recipient is zero.
+    pub fn get_protocol_fee(&self) -> (U256, Address) {
+        (self.fee_bps.get(), self.fee_recipient.get())
+    }
+
+    /// Returns the protocol fees ever collected in `token`.
+    pub fn total_fees_collected(&self, token: Address) -> U256 {
+        self.total_fees_collected.get(token)
+    }
+
+    /// Returns every token ever listed, enabled or not.
+    pub fn get_accepted_tokens(&self) -> Vec<Address> {
+        let mut result = Vec::new();
+        for i in 0..self.token_list.len() {
+            result.push(self.token_list.get(i).unwrap());
+        }
+        result
+    }
+
+    /// Returns (listed, enabled, decimals, min_fee).
+    pub fn get_token_config(&self, token: Address) -> (bool, bool, u8, U256) {
+        let config = self.tokens.get(token);
+        (
+            config.listed.get(),
+            config.enabled.get(),
+            config.decimals.get().to::<u8>(),
+            config.min_fee.get(),
+        )
+    }
+
+    /// Returns every currency ever listed, enabled or not.
+    pub fn get_currencies(&self) -> Vec<FixedBytes<3>> {
+        let mut result = Vec::new();
+        for i in 0..self.currency_list.len() {
+            result.push(self.currency_list.get(i).unwrap());
+        }
+        result
+    }
+
+    pub fn is_currency_enabled(&self, currency: FixedBytes<3>) -> bool {
+        self.currencies.get(currency).enabled.get()
+    }
+
+    /// Returns (quoter, require_rate_quotes).
+    pub fn get_quote_config(&self) -> (Address, bool) {
+        (self.quoter.get(), self.require_rate_quotes.get())
+    }
+
+    pub fn is_quote_nonce_used(&self, merchant: Address, nonce: U256) -> bool {
+        self.used_quote_nonces.get(merchant).get(nonce)
+    }
+
+    /// EIP-712 domain separator that `RateQuote` signatures are bound to.
+    pub fn domain_separator(&self) -> FixedBytes<32> {
+        self.eip712_domain().separator()
+    }
+
+    /// Returns (oracle, max_deviation_bps, max_staleness) for `currency`.
+    pub fn get_currency_oracle(&self, currency: FixedBytes<3>) -> (Address, U256, U256) {
+        let config = self.currencies.get(currency);
+        (
+            config.oracle.get(),
+            config.max_deviation_bps.get(),
+            config.max_staleness.get(),
+        )
+    }
+
+    /// Returns the merchant's preferred settlement currency (zero = contract default).
+    pub fn get_merchant_currency(&self, merchant: Address) -> FixedBytes<3> {
+        self.merchants.get(merchant).settlement_currency.get()
+    }
+
+    /// Returns (escrowed, settlement_deadline). Both are zero unless the
+    /// payment was accepted in escrow mode and is not yet settled.
+    pub fn get_payment_escrow(&self, payment_id: U256) -> (U256, U256) {
+        let payment = self.payments.get(payment_id);
+        (payment.escrowed.get(), payment.settlement_deadline.get())
+    }
+
+    /// Returns (escrow_until_paid, settlement_period).
+    pub fn get_settlement_config(&self) -> (bool, U256) {
+        (self.escrow_until_paid.get(), self.settlement_period.get())
+    }
+
+    /// Returns (opened_by, evidence_hash, opened_at, deadline, state, ruling, compensation, resolved_at).
+    /// state: 0=None 1=Open 2=Resolved 3=Withdrawn.
+    pub fn get_dispute(
+        &self,
+        payment_id: U256,
+    ) -> (Address, FixedBytes<32>, U256, U256, u8, u8, U256, U256) {
+        let dispute = self.disputes.get(payment_id);
+        (
+            dispute.opened_by.get(),
+            dispute.evidence_hash.get(),
+            dispute.opened_at.get(),
+            dispute.deadline.get(),
+            dispute.state.get().to::<u8>(),
+            dispute.ruling.get().to::<u8>(),
+            dispute.compensation.get(),
+            dispute.resolved_at.get(),
+        )
+    }
+
+    /// Returns how long, in seconds, evidence can be submitted after a dispute opens.
+    pub fn get_dispute_period(&self) -> U256 {
+        self.dispute_period.get()
+    }
+
+    /// Returns how long, in seconds, a Paid payment can be disputed after settlement.
+    pub fn get_paid_dispute_window(&self) -> U256 {
+        self.paid_dispute_window.get()
+    }
+
+    /// Returns (bank_name_hash, account_name_hash, account_number_hash).
+    /// All values are keccak256 hashes — NOT readable strings.
+    /// To get plaintext, index MerchantRegistered / MerchantUpdated events.
+    /// Check is_registered by testing if bank_name_hash != bytes32(0).
+    pub fn get_merchant_bank_details(
+        &self,
+        merchant: Address,
+    ) -> (FixedBytes<32>, FixedBytes<32>, FixedBytes<32>) {
+        let info = self.merchants.get(merchant);
+        (
+            info.bank_name.get(),
+            info.account_name.get(),
+            info.account_number.get(),
+        )
+    }
+}
+
+// ── Internal Helpers ──────────────────────────────────────────────────────────
+
+impl PaymentSet {
+    fn insert(&mut self, id: U256) {
+        if self.positions.get(id) != U256::ZERO {
+            return;
+        }
+        self.ids.push(id);
+        self.positions.setter(id).set(U256::from(self.ids.len()));
+    }
+
+    /// Moves the last id into the removed id's place.
+    fn remove(&mut self, id: U256) {
+        let position = self.positions.get(id);
+        if position == U256::ZERO {
+            return;
+        }
+        let index = position.to::<usize>() - 1;
+        let last_index = self.ids.len() - 1;
+        if index != last_index {
+            let last = self.ids.get(last_index).unwrap();
+            self.ids.setter(index).unwrap().set(last);
+            self.positions.setter(last).set(position);
+        }
+        self.ids.pop();
+        self.positions.delete(id);
+    }
+
+    /// Returns (ids, total): up to `limit` (max 100) ids after `offset`, and the set size.
+    fn page(&self, offset: U256, limit: U256) -> (Vec<U256>, U256) {
+        let len = self.ids.len();
+        let offset = offset.saturating_to::<usize>().min(len);
+        let limit = limit.saturating_to::<usize>().min(MAX_PAGE_SIZE);
+        let ids = (offset..len)
+            .take(limit)
+            .map(|i| self.ids.get(i).unwrap())
+            .collect();
+        (ids, U256::from(len))
+    }
+}
+
+/// Scales a token amount with `decimals` up to 18 decimals.
+fn normalise_amount(amount: U256, decimals: u8) -> U256 {
+    amount * U256::from(10u64).pow(U256::from(NORMALISED_DECIMALS - decimals))
+}
+
+/// `normalised` token units at `rate`, scaled by 1e18. Reverts with
+/// InvalidRate instead of wrapping when the product does not fit.
+fn fiat_amount(normalised: U256, rate: U256) -> Result<U256, SettlXError> {
+    let value = normalised
+        .checked_mul(rate)
+        .ok_or(SettlXError::InvalidRate(InvalidRate {}))?;
+    Ok(value / U256::from(10u64).pow(U256::from(NORMALISED_DECIMALS)))
+}
+
+/// Amount taken at acceptance. Records accepted before partial capture
+/// existed store zero and captured the whole authorization.
+fn captured_amount(payment: &Payment) -> U256 {
+    let captured = payment.captured_amount.get();
+    if captured == U256::ZERO {
+        payment.amount.get()
+    } else {
+        captured
+    }
+}
+
+/// Checks a move from the stored `from` status to `to` against the transition table.
+fn check_transition(from: U8, to: PaymentStatus) -> Result<(), SettlXError> {
+    let from = from.to::<u8>();
+    match PaymentStatus::from_u8(from) {
+        Some(status) if status.can_become(to) => Ok(()),
+        _ => Err(SettlXError::InvalidTransition(InvalidTransition { from, to: to as u8 })),
+    }
+}
+
+fn check_batch_size(size: usize) -> Result<(), SettlXError> {
+    if size > MAX_BATCH_SIZE {
+        return Err(SettlXError::BatchTooLarge(BatchTooLarge {
+            size: U256::from(size),
+            maxSize: U256::from(MAX_BATCH_SIZE),
+        }));
+    }
+    Ok(())
+}
+
+/// Newest-first page of `list`: skips the `offset` most recent ids and
+/// returns up to `limit` (capped at MAX_PAGE_SIZE) of those before them.
+fn page_newest_first(list: &StorageVec<StorageU256>, offset: U256, limit: U256) -> Vec<U256> {
+    let len = list.len();
+    let offset = offset.saturating_to::<usize>().min(len);
+    let limit = limit.saturating_to::<usize>().min(MAX_PAGE_SIZE);
+    (0..len - offset)
+        .rev()
+        .take(limit)
+        .map(|i| list.get(i).unwrap())
+        .collect()
+}
+
+/// Appends one batch item's outcome. Item handlers finish every check,
+/// including the status move through `set_status`, before writing anything
+/// else, so a skipped item leaves no partial state behind. A failed transfer
+/// is only skippable when the handler transfers before it writes
+/// (`transfers_after_writes == false`); otherwise it aborts the batch.
+fn record_batch_result(
+    results: &mut Vec<Bytes>,
+    result: Result<(), SettlXError>,
+    atomic: bool,
+    transfers_after_writes: bool,
+) -> Result<(), SettlXError> {
+    match result {
+        Ok(()) => results.push(Bytes::from(Vec::new())),
+        Err(err @ SettlXError::TransferFailed(_)) if transfers_after_writes => return Err(err),
+        Err(err) if atomic => return Err(err),
+        Err(err) => results.push(Bytes::from(Vec::<u8>::from(err))),
+    }
+    Ok(())
+}
+
+/// ISO 4217 codes are three upper-case ASCII letters.
+fn is_iso_4217(currency: FixedBytes<3>) -> bool {
+    currency.iter().all(|b| b.is_ascii_uppercase())
+}
+
+impl SettlX {
+    fn initialize(
+        &mut self,
+        admin: Address,
+        token_address: Address,
+        token_decimals: u8,
+        escrow_until_paid: bool,
+    ) -> Result<(), SettlXError> {
+        if self.initialized_version.get() != U64::ZERO {
+            return Err(SettlXError::AlreadyInitialized(AlreadyInitialized {}));
+        }
+        if token_address == Address::ZERO {
+            return Err(SettlXError::InvalidToken(InvalidToken {}));
+        }
+        self.stable_token.set(token_address);
+        self.configure_token(token_address, token_decimals, true)?;
+        self.configure_currency(DEFAULT_CURRENCY, true)?;
+        self.next_payment_id.set(U256::from(1));
+        self.admin.set(admin);
+        for role in ALL_ROLES {
+            self.grant(role, admin);
+        }
+        self.treasury.set(admin);
+        self.default_payment_ttl.set(U256::from(DEFAULT_PAYMENT_TTL));
+        self.max_payment_ttl.set(U256::from(MAX_PAYMENT_TTL));
+        self.dispute_period.set(U256::from(DEFAULT_DISPUTE_PERIOD));
+        self.paid_dispute_window.set(U256::from(DEFAULT_PAID_DISPUTE_WINDOW));
+        self.escrow_until_paid.set(escrow_until_paid);
+        self.settlement_period.set(U256::from(DEFAULT_SETTLEMENT_PERIOD));
+        self.pause_grace_period.set(U256::from(DEFAULT_PAUSE_GRACE_PERIOD));
+        self.upgrade_delay.set(U256::from(DEFAULT_UPGRADE_DELAY));
+        self.initialized_version.set(U64::from(VERSION));
+        log(self.vm(), Initialized { version: VERSION });
+        Ok(())
+    }
+
+    /// Address in the ERC-1967 slot of the storage we run against.
+    fn implementation(&self) -> Address {
+        let word = self
+            .vm()
+            .storage_load_bytes32(U256::from_be_bytes(IMPLEMENTATION_SLOT.0));
+        Address::from_word(word)
+    }
+
+    /// A new implementation must be a contract that reports the same
+    /// ERC-1967 slot, so an upgrade cannot brick the proxy.
+    fn check_proxiable(&self, implementation: Address) -> Result<(), SettlXError> {
+        if self.vm().code_size(implementation) == 0 {
+            return Err(SettlXError::InvalidImplementation(InvalidImplementation {}));
+        }
+        match self.view_call(implementation, IERC1822Proxiable::proxiableUUIDCall {}) {
+            Some(ret) if ret._0 == IMPLEMENTATION_SLOT => Ok(()),
+            _ => Err(SettlXError::InvalidImplementation(InvalidImplementation {})),
+        }
+    }
+
+    /// Static-calls `target` through the host and decodes the return data.
+    /// None if the call reverts or returns something that does not decode.
+    fn view_call<C: SolCall>(&self, target: Address, call: C) -> Option<C::Return> {
+        let output = self
+            .vm()
+            .static_call(&Call::new(), target, &call.abi_encode())
+            .ok()?;
+        C::abi_decode_returns(&output, true).ok()
+    }
+
+    fn only_role(&self, role: B256) -> Result<(), SettlXError> {
+        let account = self.vm().msg_sender();
+        if !self.roles.get(role).get(account) {
+            return Err(SettlXError::MissingRole(MissingRole { role, account }));
+        }
+        Ok(())
+    }
+
+    fn when_not_migrating(&self) -> Result<(), SettlXError> {
+        if self.migration_state.get().to::<u8>() == MIGRATION_ACTIVE {
+            return Err(SettlXError::MigrationInProgress(MigrationInProgress {}));
+        }
+        Ok(())
+    }
+
+    /// Every pausable entrypoint is also closed while a legacy import runs.
+    fn when_not_paused(&self, flag: u8) -> Result<(), SettlXError> {
+        self.when_not_migrating()?;
+        if self.paused.get().to::<u8>() & flag != 0 {
+            return Err(SettlXError::EnforcedPause(EnforcedPause { flag }));
+        }
+        Ok(())
+    }
+
+    fn grant(&mut self, role: B256, account: Address) {
+        let mut members = self.roles.setter(role);
+        if members.get(account) {
+            return;
+        }
+        members.setter(account).set(true);
+        drop(members);
+
+        let sender = self.vm().msg_sender();
+        log(self.vm(), RoleGranted { role, account, sender });
+    }
+
+    /// The active treasury cannot lose TREASURY_ROLE; point funds elsewhere first.
+    fn revoke(&mut self, role: B256, account: Address) -> Result<(), SettlXError> {
+        if role == TREASURY_ROLE && account == self.treasury.get() {
+            return Err(SettlXError::TreasuryRoleInUse(TreasuryRoleInUse {}));
+        }
+        let mut members = self.roles.setter(role);
+        if !members.get(account) {
+            return Ok(());
+        }
+        members.setter(account).set(false);
+        drop(members);
+
+        let sender = self.vm().msg_sender();
+        log(self.vm(), RoleRevoked { role, account, sender });
+
+        Ok(())
+    }
+
+    /// Every status change outside dispute resolution goes through here and
+    /// is checked against the transition table.
+    fn set_status(&mut self, payment_id: U256, to: PaymentStatus) -> Result<(), SettlXError> {
+        check_transition(self.payments.get(payment_id).status.get(), to)?;
+        self.release_legacy_hold(payment_id)?;
+        self.write_status(payment_id, to);
+        Ok(())
+    }
+
+    /// An imported Pending payment may only move once the migration is sealed
+    /// and the legacy contract has not accepted or rejected it in the meantime,
+    /// so it is never settled or refunded twice.
+    fn release_legacy_hold(&mut self, payment_id: U256) -> Result<(), SettlXError> {
+        if !self.payments.get(payment_id).legacy_held.get() {
+            return Ok(());
+        }
+        let held = Err(SettlXError::LegacyPaymentHeld(LegacyPaymentHeld { id: payment_id }));
+        if self.migration_state.get().to::<u8>() != MIGRATION_SEALED {
+            return held;
+        }
+        let call = ILegacySettlX::getPaymentCall { payment_id };
+        match self.view_call(self.legacy_contract.get(), call) {
+            Some(legacy) if legacy._6 == PaymentStatus::Pending as u8 => {}
+            _ => return held,
+        }
+        self.payments.setter(payment_id).legacy_held.set(false);
+        Ok(())
+    }
 
-        if payment.status.get().to::<u8>() != 1u8 {
-            return Err(SettlXError::MustBeAcceptedFirst(MustBeAcceptedFirst {}));
+    /// The only way out of Disputed; called once a dispute is withdrawn or ruled on.
+    fn leave_dispute(&mut self, payment_id: U256, to: PaymentStatus) -> Result<(), SettlXError> {
+        let from = self.payments.get(payment_id).status.get().to::<u8>();
+        if from != PaymentStatus::Disputed as u8 || !PaymentStatus::can_leave_dispute_to(to) {
+            return Err(SettlXError::InvalidTransition(InvalidTransition { from, to: to as u8 }));
         }
+        self.write_status(payment_id, to);
+        Ok(())
+    }
 
-        payment.status.set(U8::from(3u8));
+    /// Stores `to`, keeps the open-payment queues in step with it and emits
+    /// PaymentStatusChanged with the caller as actor. Callers check the move.
+    fn write_status(&mut self, payment_id: U256, to: PaymentStatus) {
+        let now = U256::from(self.vm().block_timestamp());
+        let actor = self.vm().msg_sender();
+        let mut payment = self.payments.setter(payment_id);
+        let from = payment.status.get();
+        let merchant = payment.merchant.get();
+        payment.status.set(U8::from(to as u8));
+        payment.updated_at.set(now);
+        payment.updated_by.set(actor);
+        // Only the first arrival counts: a withdrawn or ruled dispute that
+        // returns a payment to Accepted or Paid keeps the original record,
+        // and a second partial refund keeps the first refund's time.
+        match to {
+            PaymentStatus::Accepted if payment.accepted_at.get().is_zero() => {
+                payment.accepted_at.set(now);
+                payment.accepted_by.set(actor);
+            }
+            PaymentStatus::Rejected => {
+                payment.rejected_at.set(now);
+                payment.rejected_by.set(actor);
+            }
+            PaymentStatus::Paid if payment.paid_at.get().is_zero() => {
+                payment.paid_at.set(now);
+                payment.paid_by.set(actor);
+            }
+            PaymentStatus::Cancelled => payment.cancelled_at.set(now),
+            PaymentStatus::Expired => payment.expired_at.set(now),
+            PaymentStatus::Refunded if payment.refunded_at.get().is_zero() => {
+                payment.refunded_at.set(now);
+            }
+            PaymentStatus::Disputed if payment.disputed_at.get().is_zero() => {
+                payment.disputed_at.set(now);
+            }
+            PaymentStatus::Released => payment.released_at.set(now),
+            PaymentStatus::Voided => payment.voided_at.set(now),
+            _ => {}
+        }
+        drop(payment);
 
-        log(self.vm(), PaymentMarkedAsPaid { id: payment_id });
+        let (from, to) = (from.to::<u8>(), to as u8);
+        if from != to {
+            self.unindex_status(payment_id, merchant, from);
+            self.index_status(payment_id, merchant, to);
+            log(self.vm(), PaymentStatusChanged { id: payment_id, from, to, actor });
+        }
+    }
+
+    fn index_status(&mut self, payment_id: U256, merchant: Address, status: u8) {
+        match PaymentStatus::from_u8(status) {
+            Some(PaymentStatus::Pending) => {
+                self.pending_payments.insert(payment_id);
+                self.merchant_pending.setter(merchant).insert(payment_id);
+            }
+            Some(PaymentStatus::Accepted) => {
+                self.awaiting_settlement.insert(payment_id);
+                self.merchant_awaiting_settlement.setter(merchant).insert(payment_id);
+            }
+            _ => {}
+        }
+    }
+
+    fn unindex_status(&mut self, payment_id: U256, merchant: Address, status: u8) {
+        match PaymentStatus::from_u8(status) {
+            Some(PaymentStatus::Pending) => {
+                self.pending_payments.remove(payment_id);
+                self.merchant_pending.setter(merchant).remove(payment_id);
+            }
+            Some(PaymentStatus::Accepted) => {
+                self.awaiting_settlement.remove(payment_id);
+                self.merchant_awaiting_settlement.setter(merchant).remove(payment_id);
+            }
+            _ => {}
+        }
+    }
+
+    /// Walks `list` newest first from `cursor` (entries already inspected),
+    /// collecting ids whose payment is in `status`. Stops after `limit`
+    /// matches or MAX_PAGE_SCAN entries and returns where it stopped.
+    fn filter_newest_first(
+        &self,
+        list: &StorageVec<StorageU256>,
+        status: u8,
+        cursor: U256,
+        limit: U256,
+    ) -> (Vec<U256>, U256) {
+        let len = list.len();
+        let mut scanned = cursor.saturating_to::<usize>().min(len);
+        let end = scanned.saturating_add(MAX_PAGE_SCAN).min(len);
+        let limit = limit.saturating_to::<usize>().min(MAX_PAGE_SIZE);
+
+        let mut ids = Vec::new();
+        while scanned < end && ids.len() < limit {
+            let id = list.get(len - 1 - scanned).unwrap();
+            scanned += 1;
+            if self.payments.get(id).status.get().to::<u8>() == status {
+                ids.push(id);
+            }
+        }
+        (ids, U256::from(scanned))
+    }
+
+    /// Escrow a new payment from `payer`; see `pay_merchant`. Pulls the funds
+    /// before writing anything, which lets `batch_pay` skip a failed transfer.
+    fn create_payment(
+        &mut self,
+        payer: Address,
+        token: Address,
+        merchant: Address,
+        amount: U256,
+        rfce: String,
+        expires_in: U256,
+    ) -> Result<(), SettlXError> {
+        // Paying yourself would only inflate volume towards a cheaper plan.
+        if merchant == Address::ZERO || merchant == payer {
+            return Err(SettlXError::InvalidMerchant(InvalidMerchant {}));
+        }
+        if amount == U256::ZERO {
+            return Err(SettlXError::InvalidAmount(InvalidAmount {}));
+        }
+        if !self.tokens.get(token).enabled.get() {
+            return Err(SettlXError::TokenNotAccepted(TokenNotAccepted {}));
+        }
+        let ttl = if expires_in == U256::ZERO {
+            self.default_payment_ttl.get()
+        } else {
+            expires_in
+        };
+        if ttl > self.max_payment_ttl.get() {
+            return Err(SettlXError::InvalidExpiry(InvalidExpiry {}));
+        }
+
+        let contract_addr = self.vm().contract_address();
+        let current_time = self.vm().block_timestamp();
+
+        self.safe_transfer_from(token, payer, contract_addr, amount)?;
+
+        let id = self.next_payment_id.get();
+        let expires_at = U256::from(current_time).saturating_add(ttl);
+        let mut payment = self.payments.setter(id);
+        payment.id.set(id);
+        payment.payer.set(payer);
+        payment.merchant.set(merchant);
+        payment.amount.set(amount);
+        payment.timestamp.set(U256::from(current_time));
+        payment.updated_at.set(U256::from(current_time));
+        payment.updated_by.set(payer);
+        payment.rfce.set(keccak256(rfce.as_bytes()));
+        payment.status.set(U8::from(PaymentStatus::Pending as u8));
+        payment.locked_rate.set(U256::ZERO);
+        payment.expires_at.set(expires_at);
+        payment.token.set(token);
+
+        self.merchant_payments.setter(merchant).push(id);
+        self.payer_payments.setter(payer).push(id);
+        self.index_status(id, merchant, PaymentStatus::Pending as u8);
+        self.next_payment_id.set(id + U256::from(1));
+
+        log(self.vm(), PaymentCreated {
+            id,
+            payer,
+            merchant,
+            token,
+            amount,
+            rfce,
+            expiresAt: expires_at,
+        });
 
         Ok(())
     }
 
-    // ── Read-only Getters ─────────────────────────────────────────────────────
+    fn reject(&mut self, payment_id: U256, merchant: Address) -> Result<(), SettlXError> {
+        let payment = self.payments.get(payment_id);
 
-    pub fn get_merchant_payment_ids(&self, merchant: Address) -> Vec<U256> {
-        let payments = self.merchant_payments.get(merchant);
-        let mut result = Vec::new();
-        for i in 0..payments.len() {
-            result.push(payments.get(i).unwrap());
+        if payment.merchant.get() != merchant {
+            return Err(SettlXError::NotYourPayment(NotYourPayment {}));
         }
-        result
+        check_transition(payment.status.get(), PaymentStatus::Rejected)?;
+
+        let payer = payment.payer.get();
+        let amount = payment.amount.get();
+        let token = payment.token.get();
+        drop(payment);
+        self.set_status(payment_id, PaymentStatus::Rejected)?;
+        self.payments.setter(payment_id).refunded_amount.set(amount);
+
+        self.safe_transfer(token, payer, amount)?;
+
+        log(self.vm(), PaymentRejected { id: payment_id });
+
+        Ok(())
     }
 
-    pub fn get_payer_payment_ids(&self, payer: Address) -> Vec<U256> {
-        let payments = self.payer_payments.get(payer);
-        let mut result = Vec::new();
-        for i in 0..payments.len() {
-            result.push(payments.get(i).unwrap());
+    fn mark_paid(&mut self, payment_id: U256, reference: FixedBytes<32>) -> Result<(), SettlXError> {
+        let payment = self.payments.get(payment_id);
+
+        // Settling a disputed payment is the arbitrator's call, not the settler's.
+        if payment.status.get().to::<u8>() == PaymentStatus::Disputed as u8 {
+            return Err(SettlXError::PaymentDisputed(PaymentDisputed { id: payment_id }));
         }
-        result
+        check_transition(payment.status.get(), PaymentStatus::Paid)?;
+
+        let escrowed = payment.escrowed.get();
+        let token = payment.token.get();
+        drop(payment);
+        self.set_status(payment_id, PaymentStatus::Paid)?;
+        let mut payment = self.payments.setter(payment_id);
+        payment.escrowed.set(U256::ZERO);
+        payment.settlement_ref.set(reference);
+        drop(payment);
+
+        if escrowed > U256::ZERO {
+            let treasury = self.treasury.get();
+            self.safe_transfer(token, treasury, escrowed)?;
+        }
+
+        log(self.vm(), PaymentMarkedAsPaid { id: payment_id });
+
+        Ok(())
     }
 
-    /// Returns (id, payer, merchant, amount, timestamp, rfce_hash, status).
-    /// rfce is a keccak256 hash — get plaintext from PaymentCreated events.
-    /// locked_rate is not returned — get it from PaymentAccepted events.
-    pub fn get_payment(
-        &self,
+    /// Take `amount` of `token` that went back to the payer out of the
+    /// merchant's volume. Only captured amounts were ever counted, so
+    /// rejections, voids and expiries never come through here. Saturates
+    /// because imported payments were never counted.
+    fn reduce_volume(&mut self, merchant: Address, token: Address, amount: U256) {
+        let decimals = self.tokens.get(token).decimals.get().to::<u8>();
+        let mut info = self.merchants.setter(merchant);
+        let volume = info.volume.get();
+        info.volume.set(volume.saturating_sub(normalise_amount(amount, decimals)));
+    }
+
+    /// Lock `rate` on a Pending payment and take `capture` of it (None = the
+    /// whole authorization); anything not captured goes back to the payer.
+    fn accept_payment(
+        &mut self,
         payment_id: U256,
-    ) -> (U256, Address, Address, U256, U256, FixedBytes<32>, u8) {
+        merchant: Address,
+        capture: Option<U256>,
+        rate: U256,
+        currency: FixedBytes<3>,
+    ) -> Result<(), SettlXError> {
+        let now = U256::from(self.vm().block_timestamp());
+
         let payment = self.payments.get(payment_id);
-        (
-            payment.id.get(),
-            payment.payer.get(),
-            payment.merchant.get(),
-            payment.amount.get(),
-            payment.timestamp.get(),
-            payment.rfce.get(),
-            payment.status.get().to::<u8>(),
-        )
+        if payment.merchant.get() != merchant {
+            return Err(SettlXError::NotYourPayment(NotYourPayment {}));
+        }
+        check_transition(payment.status.get(), PaymentStatus::Accepted)?;
+        if now >= payment.expires_at.get() {
+            return Err(SettlXError::PaymentHasExpired(PaymentHasExpired {}));
+        }
+        drop(payment);
+        if rate == U256::ZERO {
+            return Err(SettlXError::InvalidRate(InvalidRate {}));
+        }
+
+        let currency = self.resolve_currency(merchant, currency)?;
+        let round_id = self.check_rate_against_oracle(currency, rate)?;
+
+        let payment = self.payments.get(payment_id);
+        let authorized = payment.amount.get();
+        let payer = payment.payer.get();
+        let token = payment.token.get();
+        drop(payment);
+        let amount = capture.unwrap_or(authorized);
+        if amount == U256::ZERO || amount > authorized {
+            return Err(SettlXError::InvalidAmount(InvalidAmount {}));
+        }
+        let remainder = authorized - amount;
+
+        // The merchant's plan replaces the global fee and takes its spread off
+        // the rate; a deactivated plan falls back to default terms.
+        let mut plan_id = self.merchants.get(merchant).plan_id.get();
+        let plan = self.pricing_plans.get(plan_id);
+        let (fee_bps, spread_bps) = if plan_id != U256::ZERO && plan.active.get() {
+            (plan.fee_bps.get(), plan.fx_spread_bps.get())
+        } else {
+            plan_id = U256::ZERO;
+            (self.fee_bps.get(), U256::ZERO)
+        };
+        drop(plan);
+        let rate = rate
+            .checked_mul(U256::from(BPS_DENOMINATOR) - spread_bps)
+            .ok_or(SettlXError::InvalidRate(InvalidRate {}))?
+            / U256::from(BPS_DENOMINATOR);
+        let fee = self.protocol_fee(token, amount, fee_bps)?;
+        let net = amount - fee;
+        // A rate whose fiat value does not fit is never locked.
+        let decimals = self.tokens.get(token).decimals.get().to::<u8>();
+        fiat_amount(normalise_amount(net, decimals), rate)?;
+
+        self.set_status(payment_id, PaymentStatus::Accepted)?;
+        let mut info = self.merchants.setter(merchant);
+        let volume = info.volume.get();
+        info.volume.set(volume + normalise_amount(amount, decimals));
+        drop(info);
+
+        let mut payment = self.payments.setter(payment_id);
+        payment.locked_rate.set(rate);
+        payment.currency.set(currency);
+        payment.oracle_round_id.set(round_id);
+        payment.fee.set(fee);
+        payment.plan_id.set(plan_id);
+        payment.captured_amount.set(amount);
+        payment.refunded_amount.set(remainder);
+
+        if self.escrow_until_paid.get() {
+            payment.escrowed.set(net);
+            payment.settlement_deadline.set(now.saturating_add(self.settlement_period.get()));
+            drop(payment);
+        } else {
+            drop(payment);
+            let treasury = self.treasury.get();
+            self.safe_transfer(token, treasury, net)?;
+        }
+
+        if fee > U256::ZERO {
+            let total = self.total_fees_collected.get(token);
+            self.total_fees_collected.setter(token).set(total + fee);
+            let recipient = self.fee_recipient.get();
+            self.safe_transfer(token, recipient, fee)?;
+        }
+        if remainder > U256::ZERO {
+            self.safe_transfer(token, payer, remainder)?;
+        }
+
+        log(self.vm(), PaymentAccepted { id: payment_id, lockedRate: rate, currency, fee });
+        if capture.is_some() {
+            log(self.vm(), PaymentCaptured {
+                id: payment_id,
+                capturedAmount: amount,
+                refundedAmount: remainder,
+            });
+        }
+
+        Ok(())
     }
 
-    /// Returns (bank_name_hash, account_name_hash, account_number_hash).
-    /// All values are keccak256 hashes — NOT readable strings.
-    /// To get plaintext, index MerchantRegistered / MerchantUpdated events.
-    /// Check is_registered by testing if bank_name_hash != bytes32(0).
-    pub fn get_merchant_bank_details(
+    /// Fee on an `amount` of `token`: `fee_bps` of it, but at least the
+    /// token's minimum. Zero while no fee recipient is set.
+    fn protocol_fee(&self, token: Address, amount: U256, fee_bps: U256) -> Result<U256, SettlXError> {
+        if self.fee_recipient.get() == Address::ZERO {
+            return Ok(U256::ZERO);
+        }
+        let fee = amount
+            .checked_mul(fee_bps)
+            .ok_or(SettlXError::InvalidAmount(InvalidAmount {}))?
+            / U256::from(BPS_DENOMINATOR);
+        let fee = fee.max(self.tokens.get(token).min_fee.get());
+        if fee > amount {
+            return Err(SettlXError::FeeExceedsAmount(FeeExceedsAmount { fee, amount }));
+        }
+        Ok(fee)
+    }
+
+    /// Check a quoter-signed RateQuote for `payment_id` and `merchant`, burn
+    /// its nonce and return its (currency, rate).
+    fn redeem_quote(
+        &mut self,
+        payment_id: U256,
+        merchant: Address,
+        quote: (U256, FixedBytes<3>, U256, U256, U256, Address),
+        signature: &[u8],
+    ) -> Result<(FixedBytes<3>, U256), SettlXError> {
+        let now = U256::from(self.vm().block_timestamp());
+        let (quoted_id, currency, rate, valid_until, nonce, quoted_merchant) = quote;
+
+        if quoted_merchant != merchant {
+            return Err(SettlXError::NotYourPayment(NotYourPayment {}));
+        }
+        if quoted_id != payment_id {
+            return Err(SettlXError::QuoteNotForPayment(QuoteNotForPayment { id: payment_id }));
+        }
+        // A signed quote must name its currency; the merchant default is not signed.
+        if currency == FixedBytes::ZERO {
+            return Err(SettlXError::InvalidCurrency(InvalidCurrency {}));
+        }
+        if now > valid_until {
+            return Err(SettlXError::QuoteExpired(QuoteExpired {}));
+        }
+        if self.used_quote_nonces.get(merchant).get(nonce) {
+            return Err(SettlXError::QuoteAlreadyUsed(QuoteAlreadyUsed {}));
+        }
+
+        let digest = RateQuote {
+            paymentId: payment_id,
+            currency,
+            rate,
+            validUntil: valid_until,
+            nonce,
+            merchant,
+        }
+        .eip712_signing_hash(&self.eip712_domain());
+        let quoter = self.quoter.get();
+        if quoter == Address::ZERO || self.recover_signer(digest, signature) != Some(quoter) {
+            return Err(SettlXError::InvalidSignature(InvalidSignature {}));
+        }
+
+        self.used_quote_nonces.setter(merchant).setter(nonce).set(true);
+
+        log(self.vm(), RateQuoteRedeemed { id: payment_id, merchant, nonce });
+
+        Ok((currency, rate))
+    }
+
+    fn eip712_domain(&self) -> Eip712Domain {
+        eip712_domain! {
+            name: "SettlX",
+            version: "1",
+            chain_id: self.vm().chain_id(),
+            verifying_contract: self.vm().contract_address(),
+        }
+    }
+
+    /// Recovers the signer of `digest` from a 65-byte `r || s || v` signature
+    /// via the ecrecover precompile. High-`s` signatures are rejected.
+    fn recover_signer(&self, digest: B256, signature: &[u8]) -> Option<Address> {
+        if signature.len() != 65 {
+            return None;
+        }
+        let s = U256::from_be_slice(&signature[32..64]);
+        if s > SECP256K1_HALF_ORDER {
+            return None;
+        }
+        let v = match signature[64] {
+            0 | 1 => signature[64] + 27,
+            27 | 28 => signature[64],
+            _ => return None,
+        };
+
+        let mut input = [0u8; 128];
+        input[..32].copy_from_slice(digest.as_slice());
+        input[63] = v;
+        input[64..128].copy_from_slice(&signature[..64]);
+
+        let output = self.vm().static_call(&Call::new(), ECRECOVER, &input).ok()?;
+        if output.len() != 32 {
+            return None;
+        }
+        let signer = Address::from_slice(&output[12..]);
+        (signer != Address::ZERO).then_some(signer)
+    }
+
+    fn configure_currency(
+        &mut self,
+        currency: FixedBytes<3>,
+        enabled: bool,
+    ) -> Result<(), SettlXError> {
+        if !is_iso_4217(currency) {
+            return Err(SettlXError::InvalidCurrency(InvalidCurrency {}));
+        }
+        if !self.currencies.get(currency).listed.get() {
+            self.currency_list.push(currency);
+        }
+        let mut config = self.currencies.setter(currency);
+        config.listed.set(true);
+        config.enabled.set(enabled);
+
+        log(self.vm(), CurrencyConfigured { currency, enabled });
+
+        Ok(())
+    }
+
+    /// Picks the settlement currency for `merchant`: the explicit choice,
+    /// then the merchant's profile, then the contract default.
+    fn resolve_currency(
         &self,
         merchant: Address,
-    ) -> (FixedBytes<32>, FixedBytes<32>, FixedBytes<32>) {
-        let info = self.merchants.get(merchant);
-        (
-            info.bank_name.get(),
-            info.account_name.get(),
-            info.account_number.get(),
-        )
+        requested: FixedBytes<3>,
+    ) -> Result<FixedBytes<3>, SettlXError> {
+        let mut currency = requested;
+        if currency == FixedBytes::ZERO {
+            currency = self.merchants.get(merchant).settlement_currency.get();
+        }
+        if currency == FixedBytes::ZERO {
+            currency = DEFAULT_CURRENCY;
+        }
+        if !self.currencies.get(currency).enabled.get() {
+            return Err(SettlXError::CurrencyNotEnabled(CurrencyNotEnabled {}));
+        }
+        Ok(currency)
+    }
+
+    /// Checks `rate` against the currency's oracle, if it has one, and returns
+    /// the round id it was checked against (0 when no oracle is configured).
+    ///
+    /// The feed is read as `currency` per USD; stablecoins are treated as 1 USD.
+    /// Its answer is scaled to 18 decimals to match `rate`.
+    fn check_rate_against_oracle(
+        &self,
+        currency: FixedBytes<3>,
+        rate: U256,
+    ) -> Result<U256, SettlXError> {
+        let config = self.currencies.get(currency);
+        let oracle = config.oracle.get();
+        if oracle == Address::ZERO {
+            return Ok(U256::ZERO);
+        }
+        let max_deviation_bps = config.max_deviation_bps.get();
+        let max_staleness = config.max_staleness.get();
+        drop(config);
+
+        self.check_sequencer()?;
+
+        let now = U256::from(self.vm().block_timestamp());
+        let AggregatorV3Interface::latestRoundDataReturn {
+            roundId: round_id,
+            answer,
+            updatedAt: updated_at,
+            answeredInRound: answered_in_round,
+            ..
+        } = self
+            .view_call(oracle, AggregatorV3Interface::latestRoundDataCall {})
+            .ok_or(SettlXError::InvalidOraclePrice(InvalidOraclePrice {}))?;
+        let decimals = self
+            .view_call(oracle, AggregatorV3Interface::decimalsCall {})
+            .ok_or(SettlXError::InvalidOraclePrice(InvalidOraclePrice {}))?
+            ._0;
+
+        if answer.is_negative() || answer.is_zero() {
+            return Err(SettlXError::InvalidOraclePrice(InvalidOraclePrice {}));
+        }
+        if updated_at == U256::ZERO
+            || updated_at > now
+            || now - updated_at > max_staleness
+            || answered_in_round < round_id
+        {
+            return Err(SettlXError::StaleOracle(StaleOracle {}));
+        }
+
+        // U256 operators wrap, so every product here is checked.
+        let answer = answer.into_raw();
+        let oracle_rate = if decimals <= NORMALISED_DECIMALS {
+            answer.checked_mul(U256::from(10u64).pow(U256::from(NORMALISED_DECIMALS - decimals)))
+        } else {
+            Some(answer / U256::from(10u64).pow(U256::from(decimals - NORMALISED_DECIMALS)))
+        };
+        let (oracle_rate, band) = oracle_rate
+            .filter(|oracle_rate| *oracle_rate != U256::ZERO)
+            .and_then(|oracle_rate| Some((oracle_rate, oracle_rate.checked_mul(max_deviation_bps)?)))
+            .ok_or(SettlXError::InvalidOraclePrice(InvalidOraclePrice {}))?;
+
+        let deviation = if rate > oracle_rate { rate - oracle_rate } else { oracle_rate - rate };
+        if deviation.checked_mul(U256::from(BPS_DENOMINATOR)).is_none_or(|scaled| scaled > band) {
+            return Err(SettlXError::RateOutOfBand(RateOutOfBand { rate, oracleRate: oracle_rate }));
+        }
+
+        Ok(U256::from(round_id))
+    }
+
+    /// Reverts while the L2 sequencer is down or still inside its grace period.
+    /// A no-op when no uptime feed is configured.
+    fn check_sequencer(&self) -> Result<(), SettlXError> {
+        let feed = self.sequencer_uptime_feed.get();
+        if feed == Address::ZERO {
+            return Ok(());
+        }
+
+        // Chainlink uptime feeds answer 0 while up and 1 while down; startedAt
+        // is when the current status began.
+        let AggregatorV3Interface::latestRoundDataReturn { answer, startedAt: started_at, .. } = self
+            .view_call(feed, AggregatorV3Interface::latestRoundDataCall {})
+            .ok_or(SettlXError::SequencerDown(SequencerDown {}))?;
+        if !answer.is_zero() || started_at == U256::ZERO {
+            return Err(SettlXError::SequencerDown(SequencerDown {}));
+        }
+
+        let now = U256::from(self.vm().block_timestamp());
+        if now <= started_at.saturating_add(self.sequencer_grace_period.get()) {
+            return Err(SettlXError::SequencerGracePeriodNotOver(SequencerGracePeriodNotOver {}));
+        }
+
+        Ok(())
+    }
+
+    fn configure_token(
+        &mut self,
+        token: Address,
+        decimals: u8,
+        enabled: bool,
+    ) -> Result<(), SettlXError> {
+        if decimals > NORMALISED_DECIMALS {
+            return Err(SettlXError::InvalidDecimals(InvalidDecimals {}));
+        }
+        let config = self.tokens.get(token);
+        let listed = config.listed.get();
+        if listed && config.decimals.get().to::<u8>() != decimals {
+            return Err(SettlXError::InvalidDecimals(InvalidDecimals {}));
+        }
+        drop(config);
+
+        if !listed {
+            self.token_list.push(token);
+        }
+        let mut config = self.tokens.setter(token);
+        config.listed.set(true);
+        config.decimals.set(U8::from(decimals));
+        config.enabled.set(enabled);
+
+        log(self.vm(), TokenConfigured { token, decimals, enabled });
+
+        Ok(())
+    }
+
+    /// Sends `amount` of `token` held by this contract to `to`.
+    fn safe_transfer(
+        &mut self,
+        token: Address,
+        to: Address,
+        amount: U256,
+    ) -> Result<(), SettlXError> {
+        let from = self.vm().contract_address();
+        let calldata = IERC20::transferCall { to, amount }.abi_encode();
+        self.call_token(token, from, to, amount, &calldata)
+    }
+
+    /// Pulls `amount` of `token` from `from` to `to` using this contract's allowance.
+    fn safe_transfer_from(
+        &mut self,
+        token: Address,
+        from: Address,
+        to: Address,
+        amount: U256,
+    ) -> Result<(), SettlXError> {
+        let calldata = IERC20::transferFromCall { from, to, amount }.abi_encode();
+        self.call_token(token, from, to, amount, &calldata)
+    }
+
+    /// Executes an ERC20 transfer call and reverts unless it clearly succeeded.
+    ///
+    /// A call counts as successful when it does not revert and either returns
+    /// `true` or returns no data at all (USDT-style tokens). Empty return data
+    /// is only trusted if `token` actually has code, otherwise a call to an
+    /// EOA would look like a successful transfer.
+    fn call_token(
+        &mut self,
+        token: Address,
+        from: Address,
+        to: Address,
+        amount: U256,
+        calldata: &[u8],
+    ) -> Result<(), SettlXError> {
+        let succeeded = match self.vm().call(&Call::new(), token, calldata) {
+            Ok(data) if data.is_empty() => self.vm().code_size(token) > 0,
+            Ok(data) => IERC20::transferCall::abi_decode_returns(&data, true)
+                .map(|ret| ret._0)
+                .unwrap_or(false),
+            Err(_) => false,
+        };
+        if !succeeded {
+            return Err(SettlXError::TransferFailed(TransferFailed { token, from, to, amount }));
+        }
+        Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests;
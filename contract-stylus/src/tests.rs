@@ -0,0 +1,1484 @@
+use super::*;
+use stylus_sdk::{function_selector, methods::Purity, testing::*};
+
+const ADMIN: Address = address!("00000000000000000000000000000000000000ad");
+const TOKEN: Address = address!("0000000000000000000000000000000000000070");
+const PAYER: Address = address!("00000000000000000000000000000000000000a1");
+const MERCHANT: Address = address!("00000000000000000000000000000000000000b1");
+/// 1,500 units of the settlement currency per token.
+const RATE: U256 = uint!(1_500_000_000_000_000_000_000_U256);
+const START: u64 = 1_700_000_000;
+
+fn ok<T>(result: Result<T, SettlXError>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(err) => panic!("reverted: 0x{}", hex::encode(Vec::<u8>::from(err))),
+    }
+}
+
+/// A SettlX initialised by ADMIN with TOKEN (6 decimals) as its only token.
+/// TOKEN has code, and unmocked calls return no data, so transfers succeed.
+fn setup(escrow_until_paid: bool) -> (TestVM, SettlX) {
+    let vm = TestVM::default();
+    let mut contract = SettlX::from(&vm);
+    vm.set_block_timestamp(START);
+    vm.set_code(TOKEN, vec![0xfe]);
+    vm.set_sender(ADMIN);
+    ok(contract.init(TOKEN, 6, escrow_until_paid));
+    (vm, contract)
+}
+
+/// PAYER pays MERCHANT `amount` of TOKEN; returns the new payment id.
+fn pay(vm: &TestVM, contract: &mut SettlX, amount: u64) -> U256 {
+    vm.set_sender(PAYER);
+    ok(contract.pay_merchant(TOKEN, MERCHANT, U256::from(amount), "ref".into(), U256::ZERO));
+    contract.next_payment_id.get() - U256::from(1)
+}
+
+/// MERCHANT accepts `payment_id` at RATE in the default currency.
+fn accept(vm: &TestVM, contract: &mut SettlX, payment_id: U256) {
+    vm.set_sender(MERCHANT);
+    ok(contract.accept_payment_with_rate(payment_id, RATE, FixedBytes::ZERO));
+}
+
+fn status(contract: &SettlX, payment_id: U256) -> u8 {
+    contract.payments.get(payment_id).status.get().to::<u8>()
+}
+
+/// Whether SettlX's router answers `selector` with a non-payable method.
+/// Only such a method refuses to be overridden by a payable one.
+fn routes(selector: [u8; 4]) -> bool {
+    !SettlX::__stylus_allow_override(u32::from_be_bytes(selector), Purity::Payable)
+}
+
+// ── Initialisation ────────────────────────────────────────────────────────────
+
+#[test]
+fn constructor_hands_everything_to_the_named_admin() {
+    let vm = TestVM::default();
+    let mut contract = SettlX::from(&vm);
+    let factory = address!("00000000000000000000000000000000000000d1");
+    let relayer = address!("00000000000000000000000000000000000000d2");
+    vm.set_sender(factory);
+    vm.set_tx_origin(relayer);
+
+    let result = contract.constructor(Address::ZERO, TOKEN, 6, false);
+    assert!(matches!(result, Err(SettlXError::InvalidAdmin(_))));
+
+    ok(contract.constructor(ADMIN, TOKEN, 6, false));
+    assert_eq!(contract.get_admin(), ADMIN);
+    assert_eq!(contract.get_treasury(), ADMIN);
+    for role in ALL_ROLES {
+        assert!(contract.has_role(role, ADMIN));
+        assert!(!contract.has_role(role, factory) && !contract.has_role(role, relayer));
+    }
+}
+
+// ── Admin handover ────────────────────────────────────────────────────────────
+
+const NEXT_ADMIN: Address = address!("00000000000000000000000000000000000000ae");
+
+#[test]
+fn accepted_handover_moves_every_role_and_the_treasury() {
+    let (vm, mut contract) = setup(false);
+    vm.set_sender(NEXT_ADMIN);
+    assert!(matches!(contract.transfer_admin(NEXT_ADMIN), Err(SettlXError::OnlyAdmin(_))));
+    vm.set_sender(ADMIN);
+    assert!(matches!(contract.transfer_admin(Address::ZERO), Err(SettlXError::InvalidAdmin(_))));
+    assert!(matches!(contract.transfer_admin(ADMIN), Err(SettlXError::InvalidAdmin(_))));
+    ok(contract.transfer_admin(NEXT_ADMIN));
+    assert_eq!((contract.get_admin(), contract.get_pending_admin()), (ADMIN, NEXT_ADMIN));
+    assert!(matches!(contract.accept_admin(), Err(SettlXError::NotPendingAdmin(_))));
+
+    vm.set_sender(NEXT_ADMIN);
+    ok(contract.accept_admin());
+    let transferred: AdminTransferred = last_log(&vm);
+    assert_eq!((transferred.previousAdmin, transferred.newAdmin), (ADMIN, NEXT_ADMIN));
+    assert_eq!((contract.get_admin(), contract.get_pending_admin()), (NEXT_ADMIN, Address::ZERO));
+    assert_eq!(contract.get_treasury(), NEXT_ADMIN);
+    for role in ALL_ROLES {
+        assert!(contract.has_role(role, NEXT_ADMIN) && !contract.has_role(role, ADMIN));
+    }
+}
+
+#[test]
+fn cancelled_handover_cannot_be_accepted() {
+    let (vm, mut contract) = setup(false);
+    assert!(matches!(contract.cancel_admin_transfer(), Err(SettlXError::NotPendingAdmin(_))));
+    ok(contract.transfer_admin(NEXT_ADMIN));
+    ok(contract.cancel_admin_transfer());
+    assert_eq!(contract.get_pending_admin(), Address::ZERO);
+
+    vm.set_sender(NEXT_ADMIN);
+    assert!(matches!(contract.accept_admin(), Err(SettlXError::NotPendingAdmin(_))));
+    assert_eq!(contract.get_admin(), ADMIN);
+    assert!(!contract.has_role(DEFAULT_ADMIN_ROLE, NEXT_ADMIN));
+}
+
+#[test]
+fn handover_leaves_a_separate_treasury_in_place() {
+    let (vm, mut contract) = setup(false);
+    let vault = address!("00000000000000000000000000000000000000af");
+    ok(contract.grant_role(TREASURY_ROLE, vault));
+    ok(contract.set_treasury(vault));
+    ok(contract.transfer_admin(NEXT_ADMIN));
+
+    vm.set_sender(NEXT_ADMIN);
+    ok(contract.accept_admin());
+    assert_eq!(contract.get_treasury(), vault);
+    assert!(contract.has_role(TREASURY_ROLE, vault));
+    assert!(contract.has_role(TREASURY_ROLE, NEXT_ADMIN) && !contract.has_role(TREASURY_ROLE, ADMIN));
+}
+
+// ── Upgrades ──────────────────────────────────────────────────────────────────
+
+#[test]
+fn propose_upgrade_accepts_a_settlx_implementation() {
+    let (vm, mut contract) = setup(false);
+    let proxy_impl = address!("00000000000000000000000000000000000001a1");
+    let next_impl = address!("00000000000000000000000000000000000001a2");
+    vm.set_storage(U256::from_be_bytes(IMPLEMENTATION_SLOT.0), proxy_impl.into_word());
+    vm.set_code(next_impl, vec![0xfe]);
+
+    // check_proxiable calls proxiableUUID(); a SettlX build must route it.
+    let selector = function_selector!("proxiableUUID");
+    assert!(routes(selector));
+    assert!(!routes(function_selector!("proxiableUuid")));
+    let implementation = SettlX::from(&vm);
+    vm.mock_static_call(next_impl, selector.to_vec(), Ok(implementation.proxiable_uuid().to_vec()));
+
+    ok(contract.propose_upgrade(next_impl));
+    assert_eq!(contract.get_upgrade_state().1, next_impl);
+}
+
+#[test]
+fn propose_upgrade_rejects_a_non_proxiable_implementation() {
+    let (vm, mut contract) = setup(false);
+    let next_impl = address!("00000000000000000000000000000000000001a2");
+    vm.set_storage(
+        U256::from_be_bytes(IMPLEMENTATION_SLOT.0),
+        address!("00000000000000000000000000000000000001a1").into_word(),
+    );
+    vm.set_code(next_impl, vec![0xfe]);
+
+    assert!(matches!(
+        contract.propose_upgrade(next_impl),
+        Err(SettlXError::InvalidImplementation(_))
+    ));
+}
+
+#[test]
+fn reinitialize_backfills_each_skipped_version() {
+    let (_vm, mut contract) = setup(false);
+    assert_eq!(contract.get_initialized_version(), (VERSION, VERSION));
+    assert!(matches!(contract.reinitialize(VERSION), Err(SettlXError::InvalidInitialization(_))));
+
+    // Storage as a version-1 deployment left it.
+    contract.initialized_version.set(U64::from(1));
+    contract.upgrade_delay.set(U256::ZERO);
+    contract.paid_dispute_window.set(U256::ZERO);
+    assert!(matches!(contract.reinitialize(VERSION + 1), Err(SettlXError::InvalidInitialization(_))));
+
+    ok(contract.reinitialize(2));
+    assert_eq!(contract.upgrade_delay.get(), U256::from(DEFAULT_UPGRADE_DELAY));
+    assert_eq!(contract.get_paid_dispute_window(), U256::ZERO);
+
+    ok(contract.reinitialize(3));
+    assert_eq!(contract.get_paid_dispute_window(), U256::from(DEFAULT_PAID_DISPUTE_WINDOW));
+    assert_eq!(contract.get_initialized_version(), (3, VERSION));
+}
+
+// ── Status transitions ────────────────────────────────────────────────────────
+
+#[test]
+fn disputed_has_no_exits_in_the_shared_table() {
+    use PaymentStatus::*;
+    for to in [Pending, Accepted, Rejected, Paid, Expired, Cancelled, Released, Refunded, Voided] {
+        assert!(!Disputed.can_become(to));
+    }
+    assert!(Accepted.can_become(Disputed));
+    assert!(Paid.can_become(Disputed));
+}
+
+#[test]
+fn settler_cannot_settle_a_disputed_payment() {
+    let (vm, mut contract) = setup(false);
+    let id = pay(&vm, &mut contract, 1_000_000);
+    accept(&vm, &mut contract, id);
+    ok(contract.open_dispute(id, FixedBytes::repeat_byte(1)));
+
+    vm.set_sender(ADMIN);
+    assert!(matches!(contract.mark_as_paid(id), Err(SettlXError::PaymentDisputed(_))));
+    assert_eq!(status(&contract, id), PaymentStatus::Disputed as u8);
+
+    vm.set_block_timestamp(START + DEFAULT_DISPUTE_PERIOD + 1);
+    ok(contract.rule_on_dispute(id, RULING_FORCE_SETTLE, U256::ZERO));
+    assert_eq!(status(&contract, id), PaymentStatus::Paid as u8);
+    assert_eq!(contract.get_dispute(id).4, DISPUTE_RESOLVED);
+}
+
+#[test]
+fn merchant_cannot_refund_a_disputed_payment() {
+    let (vm, mut contract) = setup(false);
+    let id = pay(&vm, &mut contract, 1_000_000);
+    accept(&vm, &mut contract, id);
+    vm.set_sender(ADMIN);
+    ok(contract.mark_as_paid(id));
+    vm.set_sender(PAYER);
+    ok(contract.open_dispute(id, FixedBytes::repeat_byte(1)));
+
+    vm.set_sender(MERCHANT);
+    assert!(matches!(
+        contract.refund_payment(id, U256::from(1_000)),
+        Err(SettlXError::PaymentDisputed(_))
+    ));
+
+    vm.set_sender(PAYER);
+    ok(contract.withdraw_dispute(id));
+    assert_eq!(status(&contract, id), PaymentStatus::Paid as u8);
+    vm.set_sender(MERCHANT);
+    ok(contract.refund_payment(id, U256::from(1_000)));
+    assert_eq!(status(&contract, id), PaymentStatus::Refunded as u8);
+}
+
+#[test]
+fn withdrawn_dispute_returns_to_the_settlement_queue() {
+    let (vm, mut contract) = setup(false);
+    let id = pay(&vm, &mut contract, 1_000_000);
+    accept(&vm, &mut contract, id);
+    ok(contract.open_dispute(id, FixedBytes::repeat_byte(1)));
+    assert_eq!(contract.get_settlement_queue(U256::ZERO, U256::from(10)).1, U256::ZERO);
+
+    ok(contract.withdraw_dispute(id));
+    assert_eq!(status(&contract, id), PaymentStatus::Accepted as u8);
+    assert_eq!(contract.get_settlement_queue(U256::ZERO, U256::from(10)), (vec![id], U256::from(1)));
+}
+
+#[test]
+fn ruling_waits_for_the_deadline_unless_both_parties_were_heard() {
+    let (vm, mut contract) = setup(false);
+    let id = pay(&vm, &mut contract, 1_000_000);
+    accept(&vm, &mut contract, id);
+    ok(contract.open_dispute(id, FixedBytes::repeat_byte(1)));
+
+    vm.set_sender(ADMIN);
+    let deadline = U256::from(START + DEFAULT_DISPUTE_PERIOD);
+    assert!(matches!(
+        contract.rule_on_dispute(id, RULING_FORCE_SETTLE, U256::ZERO),
+        Err(SettlXError::DisputeDeadlineNotReached(DisputeDeadlineNotReached { deadline: d })) if d == deadline
+    ));
+
+    // The merchant adding more evidence does not count for the payer.
+    vm.set_sender(MERCHANT);
+    ok(contract.submit_dispute_evidence(id, FixedBytes::repeat_byte(2)));
+    vm.set_sender(ADMIN);
+    assert!(contract.rule_on_dispute(id, RULING_FORCE_SETTLE, U256::ZERO).is_err());
+
+    vm.set_sender(PAYER);
+    ok(contract.submit_dispute_evidence(id, FixedBytes::repeat_byte(3)));
+    vm.set_sender(ADMIN);
+    ok(contract.rule_on_dispute(id, RULING_FORCE_SETTLE, U256::ZERO));
+    assert_eq!(status(&contract, id), PaymentStatus::Paid as u8);
+}
+
+/// A 1_000_000 payment with a 1% fee, disputed and past its deadline.
+fn disputed_with_fee(vm: &TestVM, contract: &mut SettlX) -> U256 {
+    ok(contract.set_protocol_fee(U256::from(100), FEE_RECIPIENT));
+    let id = pay(vm, contract, 1_000_000);
+    accept(vm, contract, id);
+    ok(contract.open_dispute(id, FixedBytes::repeat_byte(1)));
+    vm.set_block_timestamp(START + DEFAULT_DISPUTE_PERIOD);
+    vm.set_sender(ADMIN);
+    id
+}
+
+#[test]
+fn refund_ruling_returns_the_fee_as_well() {
+    let (vm, mut contract) = setup(true);
+    let id = disputed_with_fee(&vm, &mut contract);
+    assert_eq!(contract.payments.get(id).escrowed.get(), U256::from(990_000));
+
+    ok(contract.rule_on_dispute(id, RULING_REFUND_PAYER, U256::ZERO));
+    assert_eq!(status(&contract, id), PaymentStatus::Refunded as u8);
+    assert_eq!(contract.payments.get(id).refunded_amount.get(), U256::from(1_000_000));
+    assert_eq!(contract.payments.get(id).escrowed.get(), U256::ZERO);
+}
+
+#[test]
+fn refund_ruling_pulls_only_the_fee_from_the_treasury() {
+    let (vm, mut contract) = setup(true);
+    let id = disputed_with_fee(&vm, &mut contract);
+
+    // The escrow covers the net amount; only the fee comes from the treasury.
+    let treasury = contract.get_treasury();
+    let calldata = IERC20::transferFromCall { from: treasury, to: PAYER, amount: U256::from(10_000) }
+        .abi_encode();
+    vm.mock_call(TOKEN, calldata, Err(Vec::new()));
+    assert!(matches!(
+        contract.rule_on_dispute(id, RULING_REFUND_PAYER, U256::ZERO),
+        Err(SettlXError::TransferFailed(_))
+    ));
+}
+
+#[test]
+fn compensation_is_capped_at_the_net_amount() {
+    let (vm, mut contract) = setup(false);
+    let id = disputed_with_fee(&vm, &mut contract);
+
+    let over = contract.rule_on_dispute(id, RULING_COMPENSATE_MERCHANT, U256::from(990_001));
+    assert!(matches!(over, Err(SettlXError::InvalidAmount(_))));
+    ok(contract.rule_on_dispute(id, RULING_COMPENSATE_MERCHANT, U256::from(990_000)));
+}
+
+#[test]
+fn paid_payment_can_only_be_disputed_within_the_window() {
+    let (vm, mut contract) = setup(false);
+    let early = pay(&vm, &mut contract, 1_000_000);
+    let late = pay(&vm, &mut contract, 1_000_000);
+    accept(&vm, &mut contract, early);
+    accept(&vm, &mut contract, late);
+    vm.set_sender(ADMIN);
+    ok(contract.batch_mark_as_paid(vec![early, late], Vec::new(), true));
+
+    let closed_at = START + DEFAULT_PAID_DISPUTE_WINDOW;
+    vm.set_block_timestamp(closed_at);
+    vm.set_sender(PAYER);
+    ok(contract.open_dispute(early, FixedBytes::repeat_byte(1)));
+
+    vm.set_block_timestamp(closed_at + 1);
+    assert!(matches!(
+        contract.open_dispute(late, FixedBytes::repeat_byte(1)),
+        Err(SettlXError::DisputeWindowClosed(DisputeWindowClosed { closedAt })) if closedAt == U256::from(closed_at)
+    ));
+
+    vm.set_sender(ADMIN);
+    ok(contract.set_paid_dispute_window(U256::from(DEFAULT_PAID_DISPUTE_WINDOW + 1)));
+    vm.set_sender(PAYER);
+    ok(contract.open_dispute(late, FixedBytes::repeat_byte(1)));
+}
+
+#[test]
+fn illegal_transitions_revert_with_from_and_to() {
+    let (vm, mut contract) = setup(false);
+    let id = pay(&vm, &mut contract, 1_000_000);
+    vm.set_sender(ADMIN);
+    match contract.mark_as_paid(id) {
+        Err(SettlXError::InvalidTransition(err)) => {
+            assert_eq!((err.from, err.to), (PaymentStatus::Pending as u8, PaymentStatus::Paid as u8));
+        }
+        _ => panic!("expected InvalidTransition"),
+    }
+}
+
+// ── Open-payment queues ───────────────────────────────────────────────────────
+
+/// Every id sits at the position its `positions` entry records, and the
+/// set holds exactly `expected`, in order.
+fn assert_set(set: &PaymentSet, expected: &[u64]) {
+    let ids: Vec<U256> = (0..set.ids.len()).map(|i| set.ids.get(i).unwrap()).collect();
+    assert_eq!(ids, expected.iter().map(|&id| U256::from(id)).collect::<Vec<_>>());
+    for (i, id) in ids.iter().enumerate() {
+        assert_eq!(set.positions.get(*id), U256::from(i + 1));
+    }
+}
+
+#[test]
+fn payment_set_insert_is_idempotent() {
+    let (_vm, mut contract) = setup(false);
+    let set = &mut contract.pending_payments;
+    for id in [1u64, 2, 3, 2] {
+        set.insert(U256::from(id));
+    }
+    assert_set(set, &[1, 2, 3]);
+}
+
+#[test]
+fn payment_set_remove_middle_swaps_in_the_last_id() {
+    let (_vm, mut contract) = setup(false);
+    let set = &mut contract.pending_payments;
+    for id in [1u64, 2, 3, 4] {
+        set.insert(U256::from(id));
+    }
+    set.remove(U256::from(2));
+    assert_set(set, &[1, 4, 3]);
+    assert_eq!(set.positions.get(U256::from(2)), U256::ZERO);
+}
+
+#[test]
+fn payment_set_remove_last_and_unknown_ids() {
+    let (_vm, mut contract) = setup(false);
+    let set = &mut contract.pending_payments;
+    for id in [1u64, 2, 3] {
+        set.insert(U256::from(id));
+    }
+    set.remove(U256::from(3));
+    assert_set(set, &[1, 2]);
+    set.remove(U256::from(3));
+    set.remove(U256::from(9));
+    assert_set(set, &[1, 2]);
+    set.remove(U256::from(1));
+    set.remove(U256::from(2));
+    assert_set(set, &[]);
+    set.insert(U256::from(2));
+    assert_set(set, &[2]);
+}
+
+#[test]
+fn queues_follow_status_changes() {
+    let (vm, mut contract) = setup(false);
+    let ids: Vec<U256> = (0..4).map(|_| pay(&vm, &mut contract, 1_000_000)).collect();
+    assert_set(&contract.pending_payments, &[1, 2, 3, 4]);
+    assert_set(&contract.merchant_pending.get(MERCHANT), &[1, 2, 3, 4]);
+
+    accept(&vm, &mut contract, ids[1]);
+    vm.set_sender(MERCHANT);
+    ok(contract.reject_payment(ids[3]));
+    assert_set(&contract.pending_payments, &[1, 3]);
+    assert_set(&contract.merchant_pending.get(MERCHANT), &[1, 3]);
+    assert_set(&contract.awaiting_settlement, &[2]);
+
+    accept(&vm, &mut contract, ids[0]);
+    vm.set_sender(ADMIN);
+    ok(contract.mark_as_paid(ids[1]));
+    assert_set(&contract.pending_payments, &[3]);
+    assert_set(&contract.awaiting_settlement, &[1]);
+    assert_set(&contract.merchant_awaiting_settlement.get(MERCHANT), &[1]);
+    assert_eq!(contract.get_pending_queue(U256::ZERO, U256::from(10)), (vec![ids[2]], U256::from(1)));
+    assert_eq!(
+        contract.get_merchant_settlement_queue(MERCHANT, U256::ZERO, U256::from(10)),
+        (vec![ids[0]], U256::from(1))
+    );
+}
+
+#[test]
+fn reindex_skips_ids_already_queued() {
+    let (vm, mut contract) = setup(false);
+    let id = pay(&vm, &mut contract, 1_000_000);
+    vm.set_sender(ADMIN);
+    ok(contract.reindex_payments(vec![id, id, U256::from(99)]));
+    assert_set(&contract.pending_payments, &[1]);
+}
+
+// ── Token transfers ───────────────────────────────────────────────────────────
+
+/// PAYER tries to pay MERCHANT 1,000 of `token`; the call must fail with
+/// TransferFailed naming the pull from PAYER into the contract.
+fn assert_pay_fails(vm: &TestVM, contract: &mut SettlX, token: Address) {
+    vm.set_sender(PAYER);
+    let result = contract.pay_merchant(token, MERCHANT, U256::from(1_000), "ref".into(), U256::ZERO);
+    match result {
+        Err(SettlXError::TransferFailed(err)) => {
+            assert_eq!((err.token, err.from), (token, PAYER));
+            assert_eq!((err.to, err.amount), (contract.vm().contract_address(), U256::from(1_000)));
+        }
+        _ => panic!("expected TransferFailed"),
+    }
+}
+
+#[test]
+fn transfer_returning_false_is_a_failure() {
+    use alloy_sol_types::SolValue;
+    let (vm, mut contract) = setup(false);
+    let to = contract.vm().contract_address();
+    let calldata = IERC20::transferFromCall { from: PAYER, to, amount: U256::from(1_000) };
+    vm.mock_call(TOKEN, calldata.abi_encode(), Ok(false.abi_encode()));
+    assert_pay_fails(&vm, &mut contract, TOKEN);
+}
+
+#[test]
+fn reverting_transfer_is_a_failure() {
+    let (vm, mut contract) = setup(false);
+    fail_transfer_in(&vm, &contract, PAYER, 1_000);
+    assert_pay_fails(&vm, &mut contract, TOKEN);
+}
+
+#[test]
+fn transfer_to_a_token_without_code_is_a_failure() {
+    let (vm, mut contract) = setup(false);
+    let codeless = address!("0000000000000000000000000000000000000071");
+    ok(contract.set_accepted_token(codeless, 6, true));
+    assert_pay_fails(&vm, &mut contract, codeless);
+}
+
+// ── Capture, refunds and fees ─────────────────────────────────────────────────
+
+const FEE_RECIPIENT: Address = address!("00000000000000000000000000000000000000fe");
+
+fn capture(vm: &TestVM, contract: &mut SettlX, payment_id: U256, amount: u64) -> Result<(), SettlXError> {
+    vm.set_sender(MERCHANT);
+    contract.capture_payment(payment_id, U256::from(amount), RATE, FixedBytes::ZERO)
+}
+
+/// Makes TOKEN revert `transfer(to, amount)`.
+fn fail_transfer(vm: &TestVM, to: Address, amount: u64) {
+    let calldata = IERC20::transferCall { to, amount: U256::from(amount) }.abi_encode();
+    vm.mock_call(TOKEN, calldata, Err(Vec::new()));
+}
+
+#[test]
+fn partial_capture_refunds_the_remainder() {
+    let (vm, mut contract) = setup(false);
+    let id = pay(&vm, &mut contract, 1_000_000);
+    ok(capture(&vm, &mut contract, id, 600_000));
+
+    let amounts = contract.get_payment_amounts(id);
+    assert_eq!(amounts, (U256::from(1_000_000), U256::from(600_000), U256::from(400_000)));
+    assert_eq!(status(&contract, id), PaymentStatus::Accepted as u8);
+}
+
+#[test]
+fn partial_capture_sends_exactly_the_remainder_to_the_payer() {
+    let (vm, mut contract) = setup(false);
+    let id = pay(&vm, &mut contract, 1_000_000);
+    fail_transfer(&vm, PAYER, 400_000);
+    match capture(&vm, &mut contract, id, 600_000) {
+        Err(SettlXError::TransferFailed(err)) => {
+            assert_eq!((err.to, err.amount), (PAYER, U256::from(400_000)));
+        }
+        _ => panic!("expected the remainder transfer to the payer"),
+    }
+}
+
+#[test]
+fn capture_outside_the_authorization_reverts() {
+    let (vm, mut contract) = setup(false);
+    let id = pay(&vm, &mut contract, 1_000_000);
+    assert!(matches!(capture(&vm, &mut contract, id, 1_000_001), Err(SettlXError::InvalidAmount(_))));
+    assert!(matches!(capture(&vm, &mut contract, id, 0), Err(SettlXError::InvalidAmount(_))));
+    assert_eq!(status(&contract, id), PaymentStatus::Pending as u8);
+}
+
+#[test]
+fn refunds_stop_at_what_the_payer_paid() {
+    let (vm, mut contract) = setup(false);
+    let id = pay(&vm, &mut contract, 1_000_000);
+    ok(capture(&vm, &mut contract, id, 600_000));
+    vm.set_sender(ADMIN);
+    ok(contract.mark_as_paid(id));
+
+    // 400,000 already went back at capture; 600,000 more can be refunded.
+    vm.set_sender(MERCHANT);
+    ok(contract.refund_payment(id, U256::from(250_000)));
+    ok(contract.refund_payment(id, U256::from(350_000)));
+    assert_eq!(contract.get_payment_amounts(id).2, U256::from(1_000_000));
+    assert!(matches!(contract.refund_payment(id, U256::from(1)), Err(SettlXError::InvalidAmount(_))));
+}
+
+#[test]
+fn over_refund_cannot_wrap_past_the_bound() {
+    let (vm, mut contract) = setup(false);
+    let id = pay(&vm, &mut contract, 1_000_000);
+    accept(&vm, &mut contract, id);
+    vm.set_sender(ADMIN);
+    ok(contract.mark_as_paid(id));
+    ok(contract.refund_payment(id, U256::from(1)));
+
+    vm.set_sender(MERCHANT);
+    assert!(matches!(contract.refund_payment(id, U256::MAX), Err(SettlXError::InvalidAmount(_))));
+    assert!(matches!(contract.refund_payment(id, U256::ZERO), Err(SettlXError::InvalidAmount(_))));
+    assert!(matches!(
+        contract.refund_payment(id, U256::from(1_000_000)),
+        Err(SettlXError::InvalidAmount(_))
+    ));
+    assert_eq!(contract.get_payment_amounts(id).2, U256::from(1));
+}
+
+#[test]
+fn protocol_fee_rounds_down_and_respects_the_minimum() {
+    let (vm, mut contract) = setup(false);
+    vm.set_sender(ADMIN);
+    ok(contract.set_protocol_fee(U256::from(30), FEE_RECIPIENT));
+
+    // 0.3% of 999,999 is 2,999.997.
+    let id = pay(&vm, &mut contract, 999_999);
+    accept(&vm, &mut contract, id);
+    assert_eq!(contract.get_payment_fee(id), U256::from(2_999));
+
+    // Captured part only: 0.3% of 333 is 0.999.
+    let id = pay(&vm, &mut contract, 1_000);
+    ok(capture(&vm, &mut contract, id, 333));
+    assert_eq!(contract.get_payment_fee(id), U256::ZERO);
+    assert_eq!(contract.total_fees_collected(TOKEN), U256::from(2_999));
+
+    vm.set_sender(ADMIN);
+    ok(contract.set_token_min_fee(TOKEN, U256::from(500)));
+    let id = pay(&vm, &mut contract, 1_000);
+    ok(capture(&vm, &mut contract, id, 600));
+    assert_eq!(contract.get_payment_fee(id), U256::from(500));
+
+    let id = pay(&vm, &mut contract, 1_000);
+    assert!(matches!(capture(&vm, &mut contract, id, 400), Err(SettlXError::FeeExceedsAmount(_))));
+}
+
+#[test]
+fn rejection_refunds_without_a_fee() {
+    let (vm, mut contract) = setup(false);
+    vm.set_sender(ADMIN);
+    ok(contract.set_protocol_fee(U256::from(100), FEE_RECIPIENT));
+    let id = pay(&vm, &mut contract, 1_000_000);
+    // A refund net of the 1% fee would hit this failing transfer.
+    fail_transfer(&vm, PAYER, 990_000);
+    vm.set_sender(MERCHANT);
+    ok(contract.reject_payment(id));
+    assert_eq!(contract.get_payment_amounts(id).2, U256::from(1_000_000));
+    assert_eq!(contract.get_payment_fee(id), U256::ZERO);
+}
+
+#[test]
+fn unbounded_admin_periods_saturate_instead_of_wrapping() {
+    let (vm, mut contract) = setup(true);
+    ok(contract.set_settlement_period(U256::MAX));
+    ok(contract.set_dispute_period(U256::MAX));
+    ok(contract.set_pause_grace_period(U256::MAX));
+    let pending = pay(&vm, &mut contract, 1_000);
+    let id = pay(&vm, &mut contract, 1_000);
+    accept(&vm, &mut contract, id);
+    assert_eq!(contract.payments.get(id).settlement_deadline.get(), U256::MAX);
+
+    vm.set_sender(PAYER);
+    let result = contract.release_overdue_escrow(id);
+    assert!(matches!(result, Err(SettlXError::SettlementNotOverdue(_))));
+    ok(contract.open_dispute(id, FixedBytes::repeat_byte(1)));
+    assert_eq!(contract.get_dispute(id).3, U256::MAX);
+
+    vm.set_sender(ADMIN);
+    ok(contract.pause(PAUSE_ALL));
+    vm.set_block_timestamp(START + 365 * 24 * 3600);
+    vm.set_sender(PAYER);
+    let result = contract.emergency_withdraw(pending);
+    assert!(matches!(result, Err(SettlXError::PauseGracePeriodNotOver(_))));
+}
+
+#[test]
+fn overdue_escrow_releases_only_after_the_deadline() {
+    let (vm, mut contract) = setup(true);
+    let id = pay(&vm, &mut contract, 1_000);
+    accept(&vm, &mut contract, id);
+    let deadline = START + DEFAULT_SETTLEMENT_PERIOD;
+    assert_eq!(contract.payments.get(id).settlement_deadline.get(), U256::from(deadline));
+
+    vm.set_block_timestamp(deadline);
+    vm.set_sender(PAYER);
+    let result = contract.release_overdue_escrow(id);
+    assert!(matches!(result, Err(SettlXError::SettlementNotOverdue(_))));
+
+    vm.set_block_timestamp(deadline + 1);
+    vm.set_sender(ADMIN);
+    assert!(matches!(contract.release_overdue_escrow(id), Err(SettlXError::NotYourPayment(_))));
+    vm.set_sender(PAYER);
+    ok(contract.release_overdue_escrow(id));
+    let released: EscrowReleased = last_log(&vm);
+    assert_eq!((released.id, released.merchant, released.amount), (id, MERCHANT, U256::from(1_000)));
+    assert_eq!(status(&contract, id), PaymentStatus::Released as u8);
+    assert_eq!(contract.payments.get(id).escrowed.get(), U256::ZERO);
+}
+
+#[test]
+fn overdue_escrow_is_paid_to_the_merchant() {
+    let (vm, mut contract) = setup(true);
+    let id = pay(&vm, &mut contract, 1_000);
+    accept(&vm, &mut contract, id);
+    fail_transfer(&vm, MERCHANT, 1_000);
+
+    vm.set_block_timestamp(START + DEFAULT_SETTLEMENT_PERIOD + 1);
+    match contract.release_overdue_escrow(id) {
+        Err(SettlXError::TransferFailed(err)) => assert_eq!((err.to, err.amount), (MERCHANT, U256::from(1_000))),
+        _ => panic!("expected the payout to MERCHANT"),
+    }
+}
+
+#[test]
+fn nothing_to_release_without_escrow() {
+    let (vm, mut contract) = setup(false);
+    let id = pay(&vm, &mut contract, 1_000);
+    accept(&vm, &mut contract, id);
+    assert_eq!(status(&contract, id), PaymentStatus::Accepted as u8);
+
+    vm.set_block_timestamp(START + DEFAULT_SETTLEMENT_PERIOD + 1);
+    vm.set_sender(PAYER);
+    assert!(matches!(contract.release_overdue_escrow(id), Err(SettlXError::NotEscrowed(_))));
+}
+
+// ── Batches ───────────────────────────────────────────────────────────────────
+
+fn encoded(err: SettlXError) -> Bytes {
+    Bytes::from(Vec::<u8>::from(err))
+}
+
+fn success() -> Bytes {
+    Bytes::from(Vec::new())
+}
+
+/// Makes TOKEN revert `transferFrom(from, contract, amount)`.
+fn fail_transfer_in(vm: &TestVM, contract: &SettlX, from: Address, amount: u64) {
+    let to = contract.vm().contract_address();
+    let calldata = IERC20::transferFromCall { from, to, amount: U256::from(amount) }.abi_encode();
+    vm.mock_call(TOKEN, calldata, Err(Vec::new()));
+}
+
+#[test]
+fn batch_pay_skips_failed_items_including_failed_transfers() {
+    let (vm, mut contract) = setup(false);
+    fail_transfer_in(&vm, &contract, PAYER, 300);
+    let other = address!("00000000000000000000000000000000000000b2");
+
+    vm.set_sender(PAYER);
+    let results = ok(contract.batch_pay(
+        TOKEN,
+        vec![MERCHANT, MERCHANT, MERCHANT, other],
+        vec![U256::from(100), U256::ZERO, U256::from(300), U256::from(400)],
+        vec!["a".into(), "b".into(), "c".into(), "d".into()],
+        false,
+    ));
+
+    let to = contract.vm().contract_address();
+    assert_eq!(results.len(), 4);
+    assert_eq!(results[0], success());
+    assert_eq!(results[1], encoded(SettlXError::InvalidAmount(InvalidAmount {})));
+    assert_eq!(
+        results[2],
+        encoded(SettlXError::TransferFailed(TransferFailed {
+            token: TOKEN,
+            from: PAYER,
+            to,
+            amount: U256::from(300),
+        }))
+    );
+    assert_eq!(results[3], success());
+
+    // Only the two successful items exist, under consecutive ids.
+    assert_eq!(contract.get_payer_payment_ids(PAYER), vec![U256::from(1), U256::from(2)]);
+    assert_eq!(contract.payments.get(U256::from(2)).merchant.get(), other);
+    assert_eq!(contract.get_pending_queue(U256::ZERO, U256::from(10)).1, U256::from(2));
+}
+
+#[test]
+fn atomic_batch_pay_reverts_on_the_first_failure() {
+    let (vm, mut contract) = setup(false);
+    fail_transfer_in(&vm, &contract, PAYER, 300);
+    vm.set_sender(PAYER);
+    let result = contract.batch_pay(
+        TOKEN,
+        vec![MERCHANT, MERCHANT],
+        vec![U256::from(100), U256::from(300)],
+        vec!["a".into(), "b".into()],
+        true,
+    );
+    assert!(matches!(result, Err(SettlXError::TransferFailed(_))));
+}
+
+#[test]
+fn batch_mark_as_paid_reports_each_item() {
+    let (vm, mut contract) = setup(false);
+    let accepted = pay(&vm, &mut contract, 1_000);
+    let pending = pay(&vm, &mut contract, 1_000);
+    accept(&vm, &mut contract, accepted);
+
+    vm.set_sender(ADMIN);
+    let reference = FixedBytes::repeat_byte(7);
+    let results = ok(contract.batch_mark_as_paid(vec![accepted, pending], vec![reference; 2], false));
+    assert_eq!(results[0], success());
+    assert_eq!(
+        results[1],
+        encoded(SettlXError::InvalidTransition(InvalidTransition {
+            from: PaymentStatus::Pending as u8,
+            to: PaymentStatus::Paid as u8,
+        }))
+    );
+    assert_eq!(status(&contract, accepted), PaymentStatus::Paid as u8);
+    assert_eq!(contract.payments.get(accepted).settlement_ref.get(), reference);
+    assert_eq!(contract.payments.get(pending).settlement_ref.get(), FixedBytes::ZERO);
+}
+
+#[test]
+fn failed_transfer_after_writes_aborts_a_skipping_batch() {
+    let (vm, mut contract) = setup(true);
+    let first = pay(&vm, &mut contract, 1_000);
+    let second = pay(&vm, &mut contract, 2_000);
+    accept(&vm, &mut contract, first);
+    accept(&vm, &mut contract, second);
+    fail_transfer(&vm, ADMIN, 2_000);
+
+    vm.set_sender(ADMIN);
+    let result = contract.batch_mark_as_paid(vec![first, second], Vec::new(), false);
+    assert!(matches!(result, Err(SettlXError::TransferFailed(_))));
+}
+
+// ── Legacy migration ──────────────────────────────────────────────────────────
+
+const LEGACY: Address = address!("00000000000000000000000000000000000000e1");
+
+fn start_import(vm: &TestVM, contract: &mut SettlX) {
+    vm.set_code(LEGACY, vec![0xfe]);
+    vm.set_sender(ADMIN);
+    ok(contract.start_migration(LEGACY, TOKEN));
+}
+
+/// Makes LEGACY report payment `id` from PAYER to MERCHANT with `status`.
+fn legacy_payment(vm: &TestVM, id: u64, status: PaymentStatus, amount: u64) {
+    let call = ILegacySettlX::getPaymentCall { payment_id: U256::from(id) };
+    let ret = ILegacySettlX::getPaymentCall::abi_encode_returns(&(
+        U256::from(id),
+        PAYER,
+        MERCHANT,
+        U256::from(amount),
+        U256::from(START - 100),
+        FixedBytes::ZERO,
+        status as u8,
+    ));
+    vm.mock_static_call(LEGACY, call.abi_encode(), Ok(ret));
+}
+
+fn ids(values: &[u64]) -> Vec<U256> {
+    values.iter().map(|&v| U256::from(v)).collect()
+}
+
+#[test]
+fn import_copies_payments_into_the_right_queues() {
+    let (vm, mut contract) = setup(false);
+    start_import(&vm, &mut contract);
+    legacy_payment(&vm, 1, PaymentStatus::Pending, 1_000);
+    legacy_payment(&vm, 2, PaymentStatus::Accepted, 2_000);
+    legacy_payment(&vm, 3, PaymentStatus::Paid, 3_000);
+
+    ok(contract.import_payments(ids(&[1, 2, 3]), vec![U256::ZERO, RATE, RATE]));
+
+    assert_eq!(status(&contract, U256::from(1)), PaymentStatus::Pending as u8);
+    assert_eq!(status(&contract, U256::from(3)), PaymentStatus::Paid as u8);
+    assert_eq!(contract.payments.get(U256::from(2)).locked_rate.get(), RATE);
+    assert_eq!(contract.get_payer_payment_ids(PAYER), ids(&[1, 2, 3]));
+    assert_eq!(contract.get_pending_queue(U256::ZERO, U256::from(10)).0, ids(&[1]));
+    assert_eq!(contract.get_settlement_queue(U256::ZERO, U256::from(10)).0, ids(&[2]));
+    assert_eq!(contract.next_payment_id.get(), U256::from(4));
+    assert!(contract.payments.get(U256::from(1)).legacy_held.get());
+    assert!(!contract.payments.get(U256::from(2)).legacy_held.get());
+
+    let again = contract.import_payments(ids(&[2]), vec![RATE]);
+    assert!(matches!(again, Err(SettlXError::PaymentAlreadyImported(_))));
+}
+
+#[test]
+fn import_rejects_unknown_ids_and_oversized_batches() {
+    let (vm, mut contract) = setup(false);
+    start_import(&vm, &mut contract);
+
+    let missing = contract.import_payments(ids(&[9]), vec![U256::ZERO]);
+    assert!(matches!(missing, Err(SettlXError::LegacyPaymentNotFound(_))));
+
+    let too_many = vec![U256::from(1); MAX_BATCH_SIZE + 1];
+    let result = contract.import_payments(too_many.clone(), too_many);
+    assert!(matches!(result, Err(SettlXError::BatchTooLarge(_))));
+    let result = contract.import_merchants(vec![MERCHANT; MAX_BATCH_SIZE + 1]);
+    assert!(matches!(result, Err(SettlXError::BatchTooLarge(_))));
+}
+
+#[test]
+fn imported_pending_payment_is_held_until_the_migration_is_sealed() {
+    let (vm, mut contract) = setup(false);
+    start_import(&vm, &mut contract);
+    legacy_payment(&vm, 1, PaymentStatus::Pending, 1_000);
+    ok(contract.import_payments(ids(&[1]), vec![U256::ZERO]));
+
+    let result = contract.release_legacy_hold(U256::from(1));
+    assert!(matches!(result, Err(SettlXError::LegacyPaymentHeld(_))));
+
+    ok(contract.seal_migration());
+    vm.set_sender(MERCHANT);
+    ok(contract.reject_payment(U256::from(1)));
+    assert_eq!(status(&contract, U256::from(1)), PaymentStatus::Rejected as u8);
+    assert!(!contract.payments.get(U256::from(1)).legacy_held.get());
+}
+
+#[test]
+fn imported_pending_payment_settled_on_the_legacy_contract_cannot_move() {
+    let (vm, mut contract) = setup(false);
+    start_import(&vm, &mut contract);
+    legacy_payment(&vm, 1, PaymentStatus::Pending, 1_000);
+    ok(contract.import_payments(ids(&[1]), vec![U256::ZERO]));
+    ok(contract.seal_migration());
+
+    // The legacy merchant refunded the payer there after the import.
+    legacy_payment(&vm, 1, PaymentStatus::Rejected, 1_000);
+    vm.set_sender(MERCHANT);
+    let result = contract.reject_payment(U256::from(1));
+    assert!(matches!(result, Err(SettlXError::LegacyPaymentHeld(_))));
+    vm.set_block_timestamp(START + DEFAULT_PAYMENT_TTL + 1);
+    vm.set_sender(PAYER);
+    let result = contract.reclaim_expired_payment(U256::from(1));
+    assert!(matches!(result, Err(SettlXError::LegacyPaymentHeld(_))));
+    assert_eq!(status(&contract, U256::from(1)), PaymentStatus::Pending as u8);
+}
+
+/// Imports payment 1 as Pending, seals, then has LEGACY report it Rejected
+/// so it stays held; returns a fresh payment 2 alongside it.
+fn held_and_fresh(vm: &TestVM, contract: &mut SettlX) -> (U256, U256) {
+    start_import(vm, contract);
+    legacy_payment(vm, 1, PaymentStatus::Pending, 1_000);
+    ok(contract.import_payments(ids(&[1]), vec![U256::ZERO]));
+    ok(contract.seal_migration());
+    legacy_payment(vm, 1, PaymentStatus::Rejected, 1_000);
+    let fresh = pay(vm, contract, 1_000);
+    vm.set_sender(MERCHANT);
+    (U256::from(1), fresh)
+}
+
+#[test]
+fn skipped_batch_reject_leaves_a_held_payment_untouched() {
+    let (vm, mut contract) = setup(false);
+    let (held, fresh) = held_and_fresh(&vm, &mut contract);
+
+    let results = ok(contract.batch_reject(vec![held, fresh], false));
+    let expected = SettlXError::LegacyPaymentHeld(LegacyPaymentHeld { id: held });
+    assert_eq!(results, vec![encoded(expected), success()]);
+    assert_eq!(status(&contract, held), PaymentStatus::Pending as u8);
+    assert_eq!(contract.payments.get(held).refunded_amount.get(), U256::ZERO);
+    assert_eq!(status(&contract, fresh), PaymentStatus::Rejected as u8);
+}
+
+#[test]
+fn skipped_batch_accept_leaves_volume_untouched() {
+    let (vm, mut contract) = setup(false);
+    let (held, fresh) = held_and_fresh(&vm, &mut contract);
+
+    let results = ok(contract.batch_accept(vec![held, fresh], vec![RATE, RATE], false));
+    let expected = SettlXError::LegacyPaymentHeld(LegacyPaymentHeld { id: held });
+    assert_eq!(results, vec![encoded(expected), success()]);
+    assert_eq!(status(&contract, held), PaymentStatus::Pending as u8);
+    assert_eq!(contract.payments.get(held).locked_rate.get(), U256::ZERO);
+    assert_eq!(volume(&contract), normalised(1_000));
+}
+
+#[test]
+fn reindex_queues_imported_payments_and_ignores_unknown_ids() {
+    let (vm, mut contract) = setup(false);
+    start_import(&vm, &mut contract);
+    legacy_payment(&vm, 1, PaymentStatus::Accepted, 1_000);
+    ok(contract.import_payments(ids(&[1]), vec![RATE]));
+
+    // Simulate a record written before the queues existed.
+    contract.awaiting_settlement.remove(U256::from(1));
+    assert_eq!(contract.get_settlement_queue(U256::ZERO, U256::from(10)).1, U256::ZERO);
+
+    ok(contract.reindex_payments(ids(&[1, 42])));
+    assert_eq!(contract.get_settlement_queue(U256::ZERO, U256::from(10)).0, ids(&[1]));
+    assert_eq!(contract.get_pending_queue(U256::ZERO, U256::from(10)).1, U256::ZERO);
+
+    let result = contract.reindex_payments(vec![U256::from(1); MAX_BATCH_SIZE + 1]);
+    assert!(matches!(result, Err(SettlXError::BatchTooLarge(_))));
+}
+
+// ── Oracle band ───────────────────────────────────────────────────────────────
+
+const ORACLE: Address = address!("00000000000000000000000000000000000000f1");
+const UPTIME_FEED: Address = address!("00000000000000000000000000000000000000f2");
+/// RATE as an 8-decimal feed answer.
+const ORACLE_ANSWER: i64 = 150_000_000_000;
+const STALENESS: u64 = 3_600;
+
+/// Makes `feed` answer `latestRoundData()` with (round, answer, started_at,
+/// updated_at, answered_in_round).
+fn mock_round(vm: &TestVM, feed: Address, round: u64, answer: i64, started_at: u64, updated_at: u64, answered_in: u64) {
+    use alloy_sol_types::SolValue;
+    let data = (
+        U256::from(round),
+        alloy_primitives::I256::try_from(answer).unwrap(),
+        U256::from(started_at),
+        U256::from(updated_at),
+        U256::from(answered_in),
+    );
+    let call = AggregatorV3Interface::latestRoundDataCall {}.abi_encode();
+    vm.mock_static_call(feed, call, Ok(data.abi_encode_params()));
+}
+
+/// NGN checked against ORACLE with a 1% band, whose round 7 answers RATE.
+fn oracle_setup() -> (TestVM, SettlX, U256) {
+    let (vm, mut contract) = setup(false);
+    vm.set_code(ORACLE, vec![0xfe]);
+    ok(contract.set_currency_oracle(DEFAULT_CURRENCY, ORACLE, U256::from(100), U256::from(STALENESS)));
+    let decimals = AggregatorV3Interface::decimalsCall {}.abi_encode();
+    vm.mock_static_call(ORACLE, decimals, Ok(U256::from(8).to_be_bytes_vec()));
+    mock_round(&vm, ORACLE, 7, ORACLE_ANSWER, START - 60, START - 60, 7);
+    let id = pay(&vm, &mut contract, 1_000);
+    vm.set_sender(MERCHANT);
+    (vm, contract, id)
+}
+
+#[test]
+fn set_currency_oracle_validates_its_config() {
+    let (vm, mut contract) = setup(false);
+    let (bps, staleness) = (U256::from(100), U256::from(STALENESS));
+
+    let unlisted = FixedBytes(*b"EUR");
+    let result = contract.set_currency_oracle(unlisted, ORACLE, bps, staleness);
+    assert!(matches!(result, Err(SettlXError::CurrencyNotEnabled(_))));
+    for (bps, staleness) in [(0, STALENESS), (BPS_DENOMINATOR + 1, STALENESS), (100, 0)] {
+        let result = contract.set_currency_oracle(DEFAULT_CURRENCY, ORACLE, U256::from(bps), U256::from(staleness));
+        assert!(matches!(result, Err(SettlXError::InvalidOracleConfig(_))));
+    }
+
+    vm.set_sender(MERCHANT);
+    let result = contract.set_currency_oracle(DEFAULT_CURRENCY, ORACLE, bps, staleness);
+    assert!(matches!(result, Err(SettlXError::MissingRole(_))));
+
+    // A zero oracle switches the check off and needs no band.
+    vm.set_sender(ADMIN);
+    ok(contract.set_currency_oracle(DEFAULT_CURRENCY, Address::ZERO, U256::ZERO, U256::ZERO));
+    let id = pay(&vm, &mut contract, 1_000);
+    accept(&vm, &mut contract, id);
+    assert_eq!(contract.get_payment_oracle_round(id), U256::ZERO);
+}
+
+#[test]
+fn rate_inside_the_band_records_its_round() {
+    let (_vm, mut contract, id) = oracle_setup();
+    let rate = RATE + RATE / U256::from(100);
+    ok(contract.accept_payment_with_rate(id, rate, FixedBytes::ZERO));
+    assert_eq!(contract.get_payment_oracle_round(id), U256::from(7));
+    assert_eq!(contract.payments.get(id).locked_rate.get(), rate);
+}
+
+#[test]
+fn rate_outside_the_band_is_rejected() {
+    let (_vm, mut contract, id) = oracle_setup();
+    let rate = RATE + RATE / U256::from(100) + U256::from(1);
+    let result = contract.accept_payment_with_rate(id, rate, FixedBytes::ZERO);
+    assert!(matches!(result, Err(SettlXError::RateOutOfBand(_))));
+}
+
+#[test]
+fn huge_rate_cannot_wrap_into_the_band() {
+    let (_vm, mut contract, id) = oracle_setup();
+    // deviation * 10_000 wraps to 10_000 - (U256::MAX % 10_000) - 1.
+    let rate = RATE + U256::MAX / U256::from(BPS_DENOMINATOR) + U256::from(1);
+    let result = contract.accept_payment_with_rate(id, rate, FixedBytes::ZERO);
+    assert!(matches!(result, Err(SettlXError::RateOutOfBand(_))));
+    assert_eq!(status(&contract, id), PaymentStatus::Pending as u8);
+}
+
+#[test]
+fn stale_and_carried_over_rounds_are_rejected() {
+    let (vm, mut contract, id) = oracle_setup();
+    let updated_at = START - STALENESS - 1;
+    for (updated_at, answered_in) in [(updated_at, 7), (START + 1, 7), (0, 7), (START, 6)] {
+        mock_round(&vm, ORACLE, 7, ORACLE_ANSWER, START - 60, updated_at, answered_in);
+        let result = contract.accept_payment_with_rate(id, RATE, FixedBytes::ZERO);
+        assert!(matches!(result, Err(SettlXError::StaleOracle(_))));
+    }
+
+    mock_round(&vm, ORACLE, 7, 0, START - 60, START, 7);
+    let result = contract.accept_payment_with_rate(id, RATE, FixedBytes::ZERO);
+    assert!(matches!(result, Err(SettlXError::InvalidOraclePrice(_))));
+
+    mock_round(&vm, ORACLE, 7, ORACLE_ANSWER, START - 60, START - STALENESS, 7);
+    ok(contract.accept_payment_with_rate(id, RATE, FixedBytes::ZERO));
+}
+
+#[test]
+fn sequencer_outage_and_grace_period_block_oracle_checks() {
+    let (vm, mut contract, id) = oracle_setup();
+    vm.set_sender(ADMIN);
+    ok(contract.set_sequencer_uptime_feed(UPTIME_FEED, U256::from(STALENESS)));
+    vm.set_code(UPTIME_FEED, vec![0xfe]);
+    vm.set_sender(MERCHANT);
+
+    mock_round(&vm, UPTIME_FEED, 1, 1, START - 2 * STALENESS, START - 2 * STALENESS, 1);
+    let result = contract.accept_payment_with_rate(id, RATE, FixedBytes::ZERO);
+    assert!(matches!(result, Err(SettlXError::SequencerDown(_))));
+
+    mock_round(&vm, UPTIME_FEED, 2, 0, START - STALENESS, START - STALENESS, 2);
+    let result = contract.accept_payment_with_rate(id, RATE, FixedBytes::ZERO);
+    assert!(matches!(result, Err(SettlXError::SequencerGracePeriodNotOver(_))));
+
+    mock_round(&vm, UPTIME_FEED, 2, 0, START - STALENESS - 1, START - STALENESS - 1, 2);
+    ok(contract.accept_payment_with_rate(id, RATE, FixedBytes::ZERO));
+}
+
+// ── Rate quotes ───────────────────────────────────────────────────────────────
+
+const QUOTER: Address = address!("00000000000000000000000000000000000000c0");
+
+type Quote = (U256, FixedBytes<3>, U256, U256, U256, Address);
+
+fn quote_for(payment_id: U256, currency: FixedBytes<3>) -> Quote {
+    (payment_id, currency, RATE, U256::from(START + 60), U256::from(1), MERCHANT)
+}
+
+/// Signs `quote` as QUOTER by making the ecrecover precompile answer QUOTER
+/// for its exact EIP-712 digest and a fixed dummy signature.
+fn sign(vm: &TestVM, contract: &SettlX, quote: Quote) -> Bytes {
+    let (payment_id, currency, rate, valid_until, nonce, merchant) = quote;
+    let digest = RateQuote {
+        paymentId: payment_id,
+        currency,
+        rate,
+        validUntil: valid_until,
+        nonce,
+        merchant,
+    }
+    .eip712_signing_hash(&contract.eip712_domain());
+    let mut signature = [0u8; 65];
+    signature[31] = 1;
+    signature[63] = 1;
+    signature[64] = 27;
+
+    let mut input = [0u8; 128];
+    input[..32].copy_from_slice(digest.as_slice());
+    input[63] = 27;
+    input[64..128].copy_from_slice(&signature[..64]);
+    vm.mock_static_call(ECRECOVER, input.to_vec(), Ok(QUOTER.into_word().to_vec()));
+    Bytes::from(signature.to_vec())
+}
+
+fn quoted_setup() -> (TestVM, SettlX, U256, U256) {
+    let (vm, mut contract) = setup(false);
+    ok(contract.set_quoter(QUOTER));
+    let first = pay(&vm, &mut contract, 1_000);
+    let second = pay(&vm, &mut contract, 1_000);
+    vm.set_sender(MERCHANT);
+    (vm, contract, first, second)
+}
+
+#[test]
+fn signed_quote_locks_its_rate_and_currency() {
+    let (vm, mut contract, id, _) = quoted_setup();
+    let quote = quote_for(id, DEFAULT_CURRENCY);
+    let signature = sign(&vm, &contract, quote);
+
+    ok(contract.accept_payment_with_quote(id, quote, signature.clone()));
+    assert_eq!(status(&contract, id), PaymentStatus::Accepted as u8);
+    assert_eq!(contract.payments.get(id).locked_rate.get(), RATE);
+    assert_eq!(contract.payments.get(id).currency.get(), DEFAULT_CURRENCY);
+    assert!(contract.is_quote_nonce_used(MERCHANT, U256::from(1)));
+}
+
+#[test]
+fn quote_is_bound_to_one_payment() {
+    let (vm, mut contract, first, second) = quoted_setup();
+    let quote = quote_for(first, DEFAULT_CURRENCY);
+    let signature = sign(&vm, &contract, quote);
+
+    let result = contract.accept_payment_with_quote(second, quote, signature.clone());
+    assert!(matches!(result, Err(SettlXError::QuoteNotForPayment(_))));
+
+    // Rewriting the id in the tuple breaks the signature instead.
+    let moved = (second, quote.1, quote.2, quote.3, quote.4, quote.5);
+    let result = contract.accept_payment_with_quote(second, moved, signature);
+    assert!(matches!(result, Err(SettlXError::InvalidSignature(_))));
+    assert_eq!(status(&contract, second), PaymentStatus::Pending as u8);
+}
+
+#[test]
+fn quote_without_a_currency_is_rejected() {
+    let (vm, mut contract, id, _) = quoted_setup();
+    let quote = quote_for(id, FixedBytes::ZERO);
+    let signature = sign(&vm, &contract, quote);
+
+    let result = contract.accept_payment_with_quote(id, quote, signature);
+    assert!(matches!(result, Err(SettlXError::InvalidCurrency(_))));
+    assert!(!contract.is_quote_nonce_used(MERCHANT, U256::from(1)));
+}
+
+// ── Payment views ─────────────────────────────────────────────────────────────
+
+#[test]
+fn get_payments_is_capped_at_one_page() {
+    let (vm, mut contract) = setup(false);
+    let id = pay(&vm, &mut contract, 1_000);
+    let page = ok(contract.get_payments(vec![id; MAX_PAGE_SIZE]));
+    assert_eq!(page.len(), MAX_PAGE_SIZE);
+    assert!(page.iter().all(|details| details.id == id));
+    assert!(matches!(
+        contract.get_payments(vec![id; MAX_PAGE_SIZE + 1]),
+        Err(SettlXError::BatchTooLarge(_))
+    ));
+}
+
+#[test]
+fn details_record_when_a_payment_was_cancelled_expired_or_refunded() {
+    let (vm, mut contract) = setup(false);
+    let cancelled = pay(&vm, &mut contract, 1_000);
+    let expired = pay(&vm, &mut contract, 1_000);
+    let refunded = pay(&vm, &mut contract, 1_000);
+    accept(&vm, &mut contract, refunded);
+    vm.set_sender(ADMIN);
+    ok(contract.mark_as_paid(refunded));
+
+    vm.set_block_timestamp(START + 10);
+    vm.set_sender(PAYER);
+    ok(contract.cancel_payment(cancelled));
+    vm.set_block_timestamp(START + DEFAULT_PAYMENT_TTL + 1);
+    ok(contract.reclaim_expired_payment(expired));
+    vm.set_sender(MERCHANT);
+    ok(contract.refund_payment(refunded, U256::from(1_000)));
+
+    let page = ok(contract.get_payments(vec![cancelled, expired, refunded]));
+    let late = U256::from(START + DEFAULT_PAYMENT_TTL + 1);
+    assert_eq!(page[0].cancelledAt, U256::from(START + 10));
+    assert_eq!((page[0].expiredAt, page[0].refundedAt), (U256::ZERO, U256::ZERO));
+    assert_eq!((page[1].expiredAt, page[1].cancelledAt), (late, U256::ZERO));
+    assert_eq!((page[2].refundedAt, page[2].cancelledAt), (late, U256::ZERO));
+}
+
+#[test]
+fn details_record_the_first_dispute_and_refund_and_the_release_or_void() {
+    let (vm, mut contract) = setup(true);
+    let disputed = pay(&vm, &mut contract, 1_000);
+    accept(&vm, &mut contract, disputed);
+    let released = pay(&vm, &mut contract, 1_000);
+    accept(&vm, &mut contract, released);
+    let voided = pay(&vm, &mut contract, 1_000);
+
+    vm.set_block_timestamp(START + 10);
+    vm.set_sender(PAYER);
+    ok(contract.open_dispute(disputed, FixedBytes::repeat_byte(1)));
+    ok(contract.withdraw_dispute(disputed));
+    vm.set_sender(ADMIN);
+    ok(contract.mark_as_paid(disputed));
+    vm.set_block_timestamp(START + 20);
+    vm.set_sender(MERCHANT);
+    ok(contract.refund_payment(disputed, U256::from(400)));
+    ok(contract.void_payment(voided));
+    vm.set_block_timestamp(START + DEFAULT_SETTLEMENT_PERIOD + 1);
+    ok(contract.refund_payment(disputed, U256::from(600)));
+    vm.set_sender(PAYER);
+    ok(contract.release_overdue_escrow(released));
+
+    let page = ok(contract.get_payments(vec![disputed, released, voided]));
+    let late = U256::from(START + DEFAULT_SETTLEMENT_PERIOD + 1);
+    assert_eq!((page[0].disputedAt, page[0].refundedAt), (U256::from(START + 10), U256::from(START + 20)));
+    assert_eq!(page[0].updatedAt, late);
+    assert_eq!((page[1].releasedAt, page[1].disputedAt), (late, U256::ZERO));
+    assert_eq!((page[2].voidedAt, page[2].releasedAt), (U256::from(START + 20), U256::ZERO));
+}
+
+/// Gives PAYER and MERCHANT a history of `count` Pending payments with ids
+/// 1..=count, written straight to storage, and moves `accepted` to Accepted.
+fn long_history(contract: &mut SettlX, count: u64, accepted: &[u64]) {
+    for id in 1..=count {
+        contract.merchant_payments.setter(MERCHANT).push(U256::from(id));
+        contract.payer_payments.setter(PAYER).push(U256::from(id));
+    }
+    for &id in accepted {
+        contract.payments.setter(U256::from(id)).status.set(U8::from(PaymentStatus::Accepted as u8));
+    }
+}
+
+#[test]
+fn pages_are_capped_and_end_past_the_history() {
+    let (_vm, mut contract) = setup(false);
+    long_history(&mut contract, 600, &[]);
+
+    let page = contract.get_payer_payment_ids_page(PAYER, U256::ZERO, U256::MAX);
+    assert_eq!(page.len(), MAX_PAGE_SIZE);
+    assert_eq!((page[0], page[MAX_PAGE_SIZE - 1]), (U256::from(600), U256::from(501)));
+    let tail = contract.get_merchant_payment_ids_page(MERCHANT, U256::from(595), U256::from(10));
+    assert_eq!(tail, ids(&[5, 4, 3, 2, 1]));
+    assert!(contract.get_merchant_payment_ids_page(MERCHANT, U256::from(600), U256::from(10)).is_empty());
+    assert!(contract.get_payer_payment_ids_page(PAYER, U256::MAX, U256::from(10)).is_empty());
+}
+
+#[test]
+fn status_filter_is_capped_at_one_page() {
+    let (_vm, mut contract) = setup(false);
+    long_history(&mut contract, 600, &[]);
+    let pending = PaymentStatus::Pending as u8;
+
+    let (page, next) = contract.get_payer_payment_ids_by_status(PAYER, pending, U256::ZERO, U256::MAX);
+    assert_eq!((page.len(), page[0], next), (MAX_PAGE_SIZE, U256::from(600), U256::from(MAX_PAGE_SIZE)));
+}
+
+#[test]
+fn status_filter_cursor_continues_past_the_scan_cap() {
+    let (_vm, mut contract) = setup(false);
+    long_history(&mut contract, 600, &[1, 2, 3]);
+    let accepted = PaymentStatus::Accepted as u8;
+    let limit = U256::from(2);
+
+    // The newest 500 entries hold no match: an empty page, but not the end.
+    let (page, next) = contract.get_merchant_payment_ids_by_status(MERCHANT, accepted, U256::ZERO, limit);
+    assert_eq!((page, next), (vec![], U256::from(MAX_PAGE_SCAN)));
+    let (page, next) = contract.get_merchant_payment_ids_by_status(MERCHANT, accepted, next, limit);
+    assert_eq!((page, next), (ids(&[3, 2]), U256::from(599)));
+    let (page, next) = contract.get_merchant_payment_ids_by_status(MERCHANT, accepted, next, limit);
+    assert_eq!((page, next), (ids(&[1]), U256::from(600)));
+
+    let (page, next) = contract.get_merchant_payment_ids_by_status(MERCHANT, accepted, U256::MAX, limit);
+    assert_eq!((page, next), (vec![], U256::from(600)));
+}
+
+// ── Pausing ───────────────────────────────────────────────────────────────────
+
+fn last_log<E: alloy_sol_types::SolEvent>(vm: &TestVM) -> E {
+    let (topics, data) = vm.get_emitted_logs().pop().expect("no log emitted");
+    E::decode_raw_log(topics, &data, true).expect("unexpected log")
+}
+
+#[test]
+fn pause_events_report_the_change_and_the_resulting_mask() {
+    let (vm, mut contract) = setup(false);
+    ok(contract.pause(PAUSE_PAYMENTS | PAUSE_ACCEPTANCE));
+    ok(contract.pause(PAUSE_ACCEPTANCE | PAUSE_SETTLEMENT));
+    let paused: Paused = last_log(&vm);
+    assert_eq!((paused.flags, paused.paused), (PAUSE_SETTLEMENT, PAUSE_ALL));
+
+    ok(contract.unpause(PAUSE_PAYMENTS | PAUSE_SETTLEMENT));
+    let unpaused: Unpaused = last_log(&vm);
+    assert_eq!(unpaused.account, ADMIN);
+    assert_eq!((unpaused.flags, unpaused.paused), (PAUSE_PAYMENTS | PAUSE_SETTLEMENT, PAUSE_ACCEPTANCE));
+
+    // Clearing a flag that was not set reports nothing cleared.
+    ok(contract.unpause(PAUSE_PAYMENTS | PAUSE_ACCEPTANCE));
+    let unpaused: Unpaused = last_log(&vm);
+    assert_eq!((unpaused.flags, unpaused.paused), (PAUSE_ACCEPTANCE, 0));
+}
+
+#[test]
+fn emergency_withdraw_opens_once_the_pause_outlasts_the_grace_period() {
+    let (vm, mut contract) = setup(false);
+    let id = pay(&vm, &mut contract, 1_000);
+    let accepted = pay(&vm, &mut contract, 1_000);
+    accept(&vm, &mut contract, accepted);
+
+    vm.set_sender(PAYER);
+    assert!(matches!(contract.emergency_withdraw(id), Err(SettlXError::NotPaused(_))));
+    vm.set_sender(ADMIN);
+    ok(contract.pause(PAUSE_SETTLEMENT));
+    vm.set_block_timestamp(START + DEFAULT_PAUSE_GRACE_PERIOD);
+    vm.set_sender(PAYER);
+    assert!(matches!(contract.emergency_withdraw(id), Err(SettlXError::PauseGracePeriodNotOver(_))));
+
+    vm.set_block_timestamp(START + DEFAULT_PAUSE_GRACE_PERIOD + 1);
+    vm.set_sender(MERCHANT);
+    assert!(matches!(contract.emergency_withdraw(id), Err(SettlXError::NotYourPayment(_))));
+    vm.set_sender(PAYER);
+    assert!(matches!(contract.emergency_withdraw(accepted), Err(SettlXError::InvalidTransition(_))));
+
+    ok(contract.emergency_withdraw(id));
+    let withdrawal: EmergencyWithdrawal = last_log(&vm);
+    assert_eq!((withdrawal.id, withdrawal.payer, withdrawal.amount), (id, PAYER, U256::from(1_000)));
+    assert_eq!(status(&contract, id), PaymentStatus::Cancelled as u8);
+    assert_eq!(contract.get_payment_amounts(id).2, U256::from(1_000));
+}
+
+#[test]
+fn emergency_withdraw_leaves_a_legacy_held_payment_alone() {
+    let (vm, mut contract) = setup(false);
+    let (held, _) = held_and_fresh(&vm, &mut contract);
+    vm.set_sender(ADMIN);
+    ok(contract.pause(PAUSE_ALL));
+    vm.set_block_timestamp(START + DEFAULT_PAUSE_GRACE_PERIOD + 1);
+
+    vm.set_sender(PAYER);
+    let result = contract.emergency_withdraw(held);
+    assert!(matches!(result, Err(SettlXError::LegacyPaymentHeld(_))));
+    assert_eq!(status(&contract, held), PaymentStatus::Pending as u8);
+    assert_eq!(contract.payments.get(held).refunded_amount.get(), U256::ZERO);
+}
+
+// ── Merchant volume ───────────────────────────────────────────────────────────
+
+const ONE_TOKEN: u64 = 1_000_000;
+
+fn volume(contract: &SettlX) -> U256 {
+    contract.get_merchant_plan(MERCHANT).1
+}
+
+fn normalised(amount: u64) -> U256 {
+    normalise_amount(U256::from(amount), 6)
+}
+
+#[test]
+fn refunds_come_back_out_of_merchant_volume() {
+    let (vm, mut contract) = setup(false);
+    let id = pay(&vm, &mut contract, 2 * ONE_TOKEN);
+    ok(capture(&vm, &mut contract, id, ONE_TOKEN));
+    assert_eq!(volume(&contract), normalised(ONE_TOKEN));
+
+    vm.set_sender(ADMIN);
+    ok(contract.mark_as_paid(id));
+    vm.set_sender(MERCHANT);
+    ok(contract.refund_payment(id, U256::from(ONE_TOKEN * 4 / 10)));
+    assert_eq!(volume(&contract), normalised(ONE_TOKEN * 6 / 10));
+}
+
+#[test]
+fn refund_ruling_comes_back_out_of_merchant_volume() {
+    let (vm, mut contract) = setup(false);
+    let kept = pay(&vm, &mut contract, ONE_TOKEN);
+    let disputed = pay(&vm, &mut contract, ONE_TOKEN);
+    accept(&vm, &mut contract, kept);
+    accept(&vm, &mut contract, disputed);
+    ok(contract.open_dispute(disputed, FixedBytes::repeat_byte(1)));
+    vm.set_block_timestamp(START + DEFAULT_DISPUTE_PERIOD);
+    vm.set_sender(ADMIN);
+    ok(contract.rule_on_dispute(disputed, RULING_REFUND_PAYER, U256::ZERO));
+    assert_eq!(volume(&contract), normalised(ONE_TOKEN));
+}
+
+#[test]
+fn unaccepted_payments_never_count_towards_volume() {
+    let (vm, mut contract) = setup(false);
+    let id = pay(&vm, &mut contract, ONE_TOKEN);
+    vm.set_sender(MERCHANT);
+    ok(contract.reject_payment(id));
+    let id = pay(&vm, &mut contract, ONE_TOKEN);
+    vm.set_block_timestamp(START + DEFAULT_PAYMENT_TTL + 1);
+    ok(contract.reclaim_expired_payment(id));
+    assert_eq!(volume(&contract), U256::ZERO);
+}
+
+#[test]
+fn payer_cannot_pay_themselves() {
+    let (vm, mut contract) = setup(false);
+    vm.set_sender(MERCHANT);
+    let result = contract.pay_merchant(TOKEN, MERCHANT, U256::from(ONE_TOKEN), "self".into(), U256::ZERO);
+    assert!(matches!(result, Err(SettlXError::InvalidMerchant(_))));
+}
+
+// ── Plan spreads ──────────────────────────────────────────────────────────────
+
+/// MERCHANT registered on plan 1, which takes no fee and a 1% FX spread.
+fn spread_setup() -> (TestVM, SettlX, U256) {
+    let (vm, mut contract) = setup(false);
+    ok(contract.set_pricing_plan(U256::from(1), U256::ZERO, U256::from(100), U256::ZERO, true));
+    vm.set_sender(MERCHANT);
+    ok(contract.register_merchant_bank_details("Bank".into(), "Name".into(), "0123456789".into()));
+    vm.set_sender(ADMIN);
+    ok(contract.assign_pricing_plan(MERCHANT, U256::from(1)));
+    let id = pay(&vm, &mut contract, ONE_TOKEN);
+    vm.set_sender(MERCHANT);
+    (vm, contract, id)
+}
+
+#[test]
+fn spread_comes_off_the_locked_rate() {
+    let (_vm, mut contract, id) = spread_setup();
+    ok(contract.accept_payment_with_rate(id, RATE, FixedBytes::ZERO));
+    let locked = RATE * U256::from(99) / U256::from(100);
+    assert_eq!(contract.payments.get(id).locked_rate.get(), locked);
+    assert_eq!(ok(contract.get_fiat_amount(id)), locked);
+}
+
+#[test]
+fn rate_that_overflows_the_spread_is_rejected() {
+    let (_vm, mut contract, id) = spread_setup();
+    let rate = U256::MAX / U256::from(BPS_DENOMINATOR - 100) + U256::from(1);
+    let result = contract.accept_payment_with_rate(id, rate, FixedBytes::ZERO);
+    assert!(matches!(result, Err(SettlXError::InvalidRate(_))));
+    assert_eq!(status(&contract, id), PaymentStatus::Pending as u8);
+}
+
+#[test]
+fn rate_whose_fiat_value_overflows_is_rejected() {
+    let (_vm, mut contract, id) = spread_setup();
+    // Survives the spread, but not the multiplication by 1e18 token units.
+    let rate = U256::MAX / U256::from(BPS_DENOMINATOR);
+    let result = contract.accept_payment_with_rate(id, rate, FixedBytes::ZERO);
+    assert!(matches!(result, Err(SettlXError::InvalidRate(_))));
+    assert_eq!(volume(&contract), U256::ZERO);
+
+    // Records that already hold such a rate report it instead of wrapping.
+    contract.payments.setter(id).locked_rate.set(rate);
+    assert!(matches!(contract.get_fiat_amount(id), Err(SettlXError::InvalidRate(_))));
+    assert!(matches!(contract.get_payments(vec![id]), Err(SettlXError::InvalidRate(_))));
+}
@@ -198,6 +198,8 @@ export default function Admin() {
           inputs: [
             { type: "uint256", name: "id", indexed: true },
             { type: "uint256", name: "lockedRate", indexed: false },
+            { type: "bytes3", name: "currency", indexed: false },
+            { type: "uint256", name: "fee", indexed: false },
           ],
         },
         fromBlock: BigInt(0),
@@ -221,8 +223,10 @@ export default function Admin() {
             { type: "uint256", name: "id", indexed: true },
             { type: "address", name: "payer", indexed: true },
             { type: "address", name: "merchant", indexed: true },
+            { type: "address", name: "token" },
             { type: "uint256", name: "amount" },
             { type: "string", name: "rfce" },
+            { type: "uint256", name: "expiresAt" },
           ],
         },
         fromBlock: BigInt(0),
@@ -251,7 +255,16 @@ export default function Admin() {
             args: [id],
           });
 
-          const [pid, payer, merchant, amount, timestamp, , status] = result;
+          const [
+            pid,
+            payer,
+            merchant,
+            amount,
+            timestamp,
+            ,
+            status,
+            refunded,
+          ] = result;
 
           const ZERO_ADDRESS = "0x0000000000000000000000000000000000000000";
           if (!pid || pid === BigInt(0) || payer === ZERO_ADDRESS) {
@@ -264,8 +277,18 @@ export default function Admin() {
 
           const usdcAmount = Number(amount) / 1e6;
           const statusStr =
-            ["Pending", "Accepted", "Rejected", "Paid"][Number(status)] ||
-            "Unknown";
+            [
+              "Pending",
+              "Accepted",
+              "Rejected",
+              "Paid",
+              "Expired",
+              "Cancelled",
+              "Disputed",
+              "Refunded",
+              "Released",
+              "Voided",
+            ][Number(status)] || "Unknown";
 
           const merchantInfo = merchantDetailsMap[merchant?.toLowerCase()] || {
             bankName: "Not Registered",
@@ -291,6 +314,7 @@ export default function Admin() {
             payer,
             merchant,
             amount: usdcAmount,
+            refunded: Number(refunded) / 1e6,
             timestamp: new Date(Number(timestamp) * 1000),
             rfce: rfceDisplay,
             status: statusStr,
@@ -460,6 +484,11 @@ export default function Admin() {
             <p className="font-semibold text-gray-900 text-sm">
               {payment.amount.toFixed(2)} USDC
             </p>
+            {payment.refunded > 0 && (
+              <p className="text-xs text-red-600">
+                {payment.refunded.toFixed(2)} USDC refunded
+              </p>
+            )}
             {payment.lockedAmountNGN ? (
               <p className="text-xs text-green-700 font-medium">
                 {fmtNGN(payment.lockedAmountNGN)}
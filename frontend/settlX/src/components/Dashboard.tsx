@@ -21,6 +21,8 @@ const config = createConfig({
 
 const CONTRACT_ADDRESS = "0x4855dcefa1a1ecf8b2fbd7eae38b6f73a90f48d1";
 const USDC_USD_PRICE_FEED = "0x50834F3163758fcC1Df9973b6e91f0F0F0434aD3";
+// ISO 4217 code of the fiat leg, packed as bytes3 ("NGN")
+const NGN_CURRENCY = "0x4e474e";
 
 const NIGERIAN_BANKS = [
   "Access Bank",
@@ -465,6 +467,8 @@ export default function Dashboard() {
             inputs: [
               { type: "uint256", name: "id", indexed: true },
               { type: "uint256", name: "lockedRate", indexed: false },
+              { type: "bytes3", name: "currency", indexed: false },
+              { type: "uint256", name: "fee", indexed: false },
             ],
           },
           fromBlock: BigInt(0),
@@ -478,8 +482,10 @@ export default function Dashboard() {
               { type: "uint256", name: "id", indexed: true },
               { type: "address", name: "payer", indexed: true },
               { type: "address", name: "merchant", indexed: true },
+              { type: "address", name: "token" },
               { type: "uint256", name: "amount" },
               { type: "string", name: "rfce" },
+              { type: "uint256", name: "expiresAt" },
             ],
           },
           fromBlock: BigInt(0),
@@ -512,7 +518,16 @@ export default function Dashboard() {
               args: [id],
             });
 
-            const [pid, payer, merchant, amount, timestamp, , status] = result;
+            const [
+              pid,
+              payer,
+              merchant,
+              amount,
+              timestamp,
+              ,
+              status,
+              refunded,
+            ] = result;
             const usdcAmount = Number(amount) / 1e6;
             const rawLockedRate = lockedRateMap[pid.toString()];
             const lockedRateNGN = rawLockedRate
@@ -525,14 +540,25 @@ export default function Dashboard() {
             const rfceDisplay =
               rfceMap[pid.toString()] || `Ref-${pid.toString()}`;
             const statusStr =
-              ["Pending", "Accepted", "Rejected", "Paid"][Number(status)] ||
-              "Unknown";
+              [
+                "Pending",
+                "Accepted",
+                "Rejected",
+                "Paid",
+                "Expired",
+                "Cancelled",
+                "Disputed",
+                "Refunded",
+                "Released",
+                "Voided",
+              ][Number(status)] || "Unknown";
 
             return {
               id: pid.toString(),
               payer,
               merchant,
               amount: usdcAmount,
+              refunded: Number(refunded) / 1e6,
               ngnAmount: lockedAmountNGN ?? liveNgnAmount,
               lockedAmountNGN,
               lockedRateNGN,
@@ -584,7 +610,7 @@ export default function Dashboard() {
         address: CONTRACT_ADDRESS,
         abi: contractABI,
         functionName: "acceptPaymentWithRate",
-        args: [BigInt(paymentId), ngnAmountInWei],
+        args: [BigInt(paymentId), ngnAmountInWei, NGN_CURRENCY],
         maxFeePerGas: BigInt(25_000_000),
         maxPriorityFeePerGas: BigInt(1_000_000),
       });
@@ -1188,6 +1214,11 @@ export default function Dashboard() {
                         </td>
                         <td className="text-right p-4 font-semibold text-gray-900">
                           {payment.amount.toFixed(3)}
+                          {payment.refunded > 0 && (
+                            <p className="text-xs font-normal text-red-600 mt-0.5">
+                              {payment.refunded.toFixed(3)} refunded
+                            </p>
+                          )}
                         </td>
                         <td className="text-right p-4">
                           <p className="font-semibold text-gray-900">
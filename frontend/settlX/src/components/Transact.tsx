@@ -22,6 +22,8 @@ const config = createConfig({
 const USDC_ADDRESS = "0x75faf114eafb1BDbe2F0316DF893fd58CE46AA4d";
 const CONTRACT_ADDRESS = "0x4855dcefa1a1ecf8b2fbd7eae38b6f73a90f48d1";
 const USDC_USD_PRICE_FEED = "0x50834F3163758fcC1Df9973b6e91f0F0F0434aD3";
+// Seconds until an unanswered payment can be reclaimed; 0 = contract default
+const PAYMENT_EXPIRES_IN = BigInt(0);
 
 export default function Transact() {
   const { address } = useAccount();
@@ -110,8 +112,10 @@ export default function Transact() {
               { type: "uint256", name: "id", indexed: true },
               { type: "address", name: "payer", indexed: true },
               { type: "address", name: "merchant", indexed: true },
+              { type: "address", name: "token" },
               { type: "uint256", name: "amount" },
               { type: "string", name: "rfce" },
+              { type: "uint256", name: "expiresAt" },
             ],
           },
           fromBlock: BigInt(0),
@@ -135,6 +139,8 @@ export default function Transact() {
             inputs: [
               { type: "uint256", name: "id", indexed: true },
               { type: "uint256", name: "lockedRate" },
+              { type: "bytes3", name: "currency" },
+              { type: "uint256", name: "fee" },
             ],
           },
           fromBlock: BigInt(0),
@@ -160,13 +166,31 @@ export default function Transact() {
                 args: [id],
               });
 
-              const [pid, payer, merchant, amount, timestamp, , status] =
-                result;
+              const [
+                pid,
+                payer,
+                merchant,
+                amount,
+                timestamp,
+                ,
+                status,
+                refunded,
+              ] = result;
 
               const usdcAmount = Number(amount) / 1e6;
               const statusStr =
-                ["Pending", "Accepted", "Rejected", "Paid"][Number(status)] ||
-                "Unknown";
+                [
+                  "Pending",
+                  "Accepted",
+                  "Rejected",
+                  "Paid",
+                  "Expired",
+                  "Cancelled",
+                  "Disputed",
+                  "Refunded",
+                  "Released",
+                  "Voided",
+                ][Number(status)] || "Unknown";
 
               // Real rfce from PaymentCreated event
               const rfceDisplay =
@@ -188,6 +212,7 @@ export default function Transact() {
                 payer,
                 merchant,
                 amount: usdcAmount,
+                refunded: Number(refunded) / 1e6,
                 // Show locked NGN for accepted/paid; live rate for pending/rejected
                 ngnAmount: lockedAmountNGN ?? liveNgnAmount,
                 lockedAmountNGN,
@@ -250,7 +275,13 @@ export default function Transact() {
         address: CONTRACT_ADDRESS,
         abi: contractABI,
         functionName: "payMerchant",
-        args: [merchantAddr, amountInWei, reference],
+        args: [
+          USDC_ADDRESS,
+          merchantAddr,
+          amountInWei,
+          reference,
+          PAYMENT_EXPIRES_IN,
+        ],
         maxFeePerGas: BigInt(25_000_000),
         maxPriorityFeePerGas: BigInt(1_000_000),
       });
@@ -558,6 +589,11 @@ export default function Transact() {
                             </td>
                             <td className="text-right p-4 font-semibold text-gray-900">
                               {payment.amount.toFixed(3)}
+                              {payment.refunded > 0 && (
+                                <p className="text-xs font-normal text-red-600 mt-0.5">
+                                  {payment.refunded.toFixed(3)} refunded
+                                </p>
+                              )}
                             </td>
                             <td className="text-right p-4">
                               <p className="font-semibold text-gray-900">